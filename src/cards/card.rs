@@ -0,0 +1,148 @@
+//! Playing cards, tarot cards, and custom (user-named) cards
+
+use std::fmt::{self, Display};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Suit {
+    Clubs,
+    Diamonds,
+    Hearts,
+    Spades,
+    // The minor arcana suits of a tarot deck
+    Wands,
+    Cups,
+    Swords,
+    Pentacles,
+}
+
+impl Suit {
+    pub const STANDARD: [Suit; 4] = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+    pub const TAROT: [Suit; 4] = [Suit::Wands, Suit::Cups, Suit::Swords, Suit::Pentacles];
+
+    pub fn is_tarot(&self) -> bool {
+        Self::TAROT.contains(self)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rank {
+    Ace,
+    /// A number card from 2 to 10
+    Number(u8),
+    /// Only found in tarot decks
+    Page,
+    /// Only found in tarot decks
+    Knight,
+    /// Only found in standard decks
+    Jack,
+    Queen,
+    King,
+}
+
+impl Rank {
+    fn number_ranks() -> impl Iterator<Item = Rank> {
+        (2..=10).map(Rank::Number)
+    }
+
+    /// Ace through King
+    pub fn standard() -> impl Iterator<Item = Rank> {
+        std::iter::once(Rank::Ace)
+            .chain(Self::number_ranks())
+            .chain([Rank::Jack, Rank::Queen, Rank::King])
+    }
+
+    /// Ace through King, with a Page and Knight instead of a Jack
+    pub fn tarot() -> impl Iterator<Item = Rank> {
+        std::iter::once(Rank::Ace)
+            .chain(Self::number_ranks())
+            .chain([Rank::Page, Rank::Knight, Rank::Queen, Rank::King])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JokerColor {
+    Red,
+    Black,
+}
+
+/// The names of the major arcana of a tarot deck, in order
+const MAJOR_ARCANA: [&str; 22] = [
+    "The Fool",
+    "The Magician",
+    "The High Priestess",
+    "The Empress",
+    "The Emperor",
+    "The Hierophant",
+    "The Lovers",
+    "The Chariot",
+    "Strength",
+    "The Hermit",
+    "Wheel of Fortune",
+    "Justice",
+    "The Hanged Man",
+    "Death",
+    "Temperance",
+    "The Devil",
+    "The Tower",
+    "The Star",
+    "The Moon",
+    "The Sun",
+    "Judgement",
+    "The World",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Card {
+    /// A card with a rank and suit, e.g. the
+    /// queen of hearts or the ace of cups
+    Suited { rank: Rank, suit: Suit },
+    Joker(JokerColor),
+    /// One of the 22 tarot trumps, numbered from 0 (The Fool)
+    /// to 21 (The World)
+    MajorArcana(u8),
+    /// A card from a user-defined deck, e.g. "Goblin"
+    Custom(String),
+}
+
+impl Card {
+    pub fn major_arcana() -> impl Iterator<Item = Card> {
+        (0..MAJOR_ARCANA.len() as u8).map(Card::MajorArcana)
+    }
+}
+
+impl Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // Standard cards are written the short way, e.g. "10♥"
+            Card::Suited { rank, suit } if !suit.is_tarot() => {
+                let rank = match rank {
+                    Rank::Ace => "A".to_owned(),
+                    Rank::Number(n) => n.to_string(),
+                    Rank::Page => "P".to_owned(),
+                    Rank::Knight => "Kn".to_owned(),
+                    Rank::Jack => "J".to_owned(),
+                    Rank::Queen => "Q".to_owned(),
+                    Rank::King => "K".to_owned(),
+                };
+                let suit = match suit {
+                    Suit::Clubs => '♣',
+                    Suit::Diamonds => '♦',
+                    Suit::Hearts => '♥',
+                    _ => '♠',
+                };
+                write!(f, "{rank}{suit}")
+            }
+            // Tarot cards are written out in full, e.g. "Page of Cups"
+            Card::Suited { rank, suit } => {
+                match rank {
+                    Rank::Number(n) => write!(f, "{n}")?,
+                    rank => write!(f, "{rank:?}")?,
+                }
+                write!(f, " of {suit:?}")
+            }
+            Card::Joker(color) => write!(f, "{color:?} Joker"),
+            Card::MajorArcana(n) => write!(f, "{}", MAJOR_ARCANA[*n as usize]),
+            Card::Custom(name) => write!(f, "{name}"),
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use rand::Rng;
+
+use super::card::{Card, JokerColor, Rank, Suit};
+
+/// Which cards a new deck is made of
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DeckKind {
+    /// The standard 52 cards, plus a red and black joker if `jokers` is true
+    Standard { jokers: bool },
+    /// The 78 cards of a tarot deck
+    Tarot,
+    /// A deck with one card for each of the given names
+    Custom(Vec<String>),
+}
+
+impl DeckKind {
+    fn cards(&self) -> Vec<Card> {
+        match self {
+            DeckKind::Standard { jokers } => {
+                let mut cards: Vec<Card> = Suit::STANDARD
+                    .into_iter()
+                    .flat_map(|suit| Rank::standard().map(move |rank| Card::Suited { rank, suit }))
+                    .collect();
+                if *jokers {
+                    cards.push(Card::Joker(JokerColor::Red));
+                    cards.push(Card::Joker(JokerColor::Black));
+                }
+                cards
+            }
+            DeckKind::Tarot => Card::major_arcana()
+                .chain(
+                    Suit::TAROT
+                        .into_iter()
+                        .flat_map(|suit| Rank::tarot().map(move |rank| Card::Suited { rank, suit })),
+                )
+                .collect(),
+            DeckKind::Custom(names) => names.iter().cloned().map(Card::Custom).collect(),
+        }
+    }
+}
+
+/// A deck of cards that is drawn from without replacement.
+/// Cards that have been drawn stay out of the deck until it is shuffled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deck {
+    /// The cards that can still be drawn. These are kept in order;
+    /// instead of shuffling up front, each draw takes a random card
+    draw_pile: Vec<Card>,
+    /// The cards drawn since the last discard or shuffle
+    drawn: Vec<Card>,
+    discard_pile: Vec<Card>,
+}
+
+impl Deck {
+    pub fn new(kind: &DeckKind) -> Self {
+        Deck {
+            draw_pile: kind.cards(),
+            drawn: vec![],
+            discard_pile: vec![],
+        }
+    }
+
+    /// Draw up to `amount` cards, returning fewer if the deck runs out
+    pub fn draw<R: Rng + ?Sized>(&mut self, amount: usize, rng: &mut R) -> Vec<Card> {
+        let amount = amount.min(self.draw_pile.len());
+        let cards: Vec<Card> = (0..amount)
+            .map(|_| {
+                let index = rng.gen_range(0..self.draw_pile.len());
+                self.draw_pile.swap_remove(index)
+            })
+            .collect();
+        self.drawn.extend(cards.iter().cloned());
+        cards
+    }
+
+    /// Put the drawn cards on the discard pile,
+    /// returning how many cards were discarded
+    pub fn discard(&mut self) -> usize {
+        let amount = self.drawn.len();
+        self.discard_pile.append(&mut self.drawn);
+        amount
+    }
+
+    /// Put every drawn and discarded card back into the deck
+    pub fn shuffle(&mut self) {
+        self.draw_pile.append(&mut self.drawn);
+        self.draw_pile.append(&mut self.discard_pile);
+    }
+
+    /// How many cards are left to draw
+    pub fn remaining(&self) -> usize {
+        self.draw_pile.len()
+    }
+
+    /// How many cards are in the deck, including drawn and discarded ones
+    pub fn len(&self) -> usize {
+        self.draw_pile.len() + self.drawn.len() + self.discard_pile.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for Deck {
+    /// The standard 52 cards, without jokers
+    fn default() -> Self {
+        Deck::new(&DeckKind::Standard { jokers: false })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::thread_rng;
+
+    use super::{Deck, DeckKind};
+
+    #[test]
+    fn deck_sizes() {
+        assert_eq!(Deck::new(&DeckKind::Standard { jokers: false }).len(), 52);
+        assert_eq!(Deck::new(&DeckKind::Standard { jokers: true }).len(), 54);
+        assert_eq!(Deck::new(&DeckKind::Tarot).len(), 78);
+        assert_eq!(Deck::new(&DeckKind::Custom(vec!["a".to_owned(), "b".to_owned()])).len(), 2);
+    }
+
+    #[test]
+    fn draw_without_replacement() {
+        let mut deck = Deck::default();
+        let mut drawn = deck.draw(50, &mut thread_rng());
+        drawn.extend(deck.draw(5, &mut thread_rng()));
+        assert_eq!(drawn.len(), 52);
+        assert_eq!(deck.remaining(), 0);
+        for (i, card) in drawn.iter().enumerate() {
+            assert!(!drawn[i + 1..].contains(card));
+        }
+
+        assert_eq!(deck.discard(), 52);
+        assert_eq!(deck.remaining(), 0);
+        deck.shuffle();
+        assert_eq!(deck.remaining(), 52);
+    }
+}
@@ -0,0 +1,34 @@
+use std::fmt::{self, Display};
+
+use super::deck::DeckKind;
+
+/// A command that reads or changes the state of the current deck
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DeckCommand {
+    /// Replace the current deck with a fresh one, e.g. "deck new jokers"
+    New(DeckKind),
+    /// Draw the given number of cards, e.g. "draw 5"
+    Draw(usize),
+    /// Put every card back in the deck, e.g. "deck shuffle"
+    Shuffle,
+    /// Count the cards left to draw, e.g. "deck remaining"
+    Remaining,
+    /// Put the cards drawn so far on the discard pile, e.g. "deck discard"
+    Discard,
+}
+
+impl Display for DeckCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeckCommand::New(DeckKind::Standard { jokers: false }) => f.write_str("deck new"),
+            DeckCommand::New(DeckKind::Standard { jokers: true }) => f.write_str("deck new jokers"),
+            DeckCommand::New(DeckKind::Tarot) => f.write_str("deck new tarot"),
+            DeckCommand::New(DeckKind::Custom(cards)) => write!(f, "deck new custom {}", cards.join(", ")),
+            DeckCommand::Draw(amount) => write!(f, "draw {amount}"),
+            DeckCommand::Shuffle => f.write_str("deck shuffle"),
+            DeckCommand::Remaining => f.write_str("deck remaining"),
+            DeckCommand::Discard => f.write_str("deck discard"),
+        }
+    }
+}
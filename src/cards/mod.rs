@@ -0,0 +1,3 @@
+pub mod card;
+pub mod deck;
+pub mod deck_command;
@@ -0,0 +1,239 @@
+//! Running the binary without the REPL, e.g. `rand_api "3d6+2"`
+//! or `echo "d20" | rand_api`, so that it can be used in scripts
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use rand_api::{help::help, validate, Session};
+
+use crate::repl::render::Renderer;
+
+/// How results are printed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// e.g. "2d6 [3, 5] + 1 = 9"
+    #[default]
+    Text,
+    /// A JSON document per line, for other programs to read
+    Json,
+}
+
+impl Format {
+    fn parse(format: &str) -> Result<Self, String> {
+        match format {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("unknown format \"{format}\" (it should be \"text\" or \"json\")")),
+        }
+    }
+}
+
+/// Where `rand_api serve` listens if it isn't given an address
+pub const DEFAULT_SERVE_ADDRESS: &str = "127.0.0.1:8080";
+
+/// The options and expressions the binary was run with
+#[derive(Debug, Default, PartialEq)]
+pub struct Args {
+    pub no_color: bool,
+    pub format: Format,
+    /// The address to serve the HTTP API on, for `rand_api serve [ADDRESS]`
+    pub serve: Option<String>,
+    /// A file to evaluate line by line, for `rand_api --file rolls.txt`
+    pub file: Option<PathBuf>,
+    /// Expressions to evaluate instead of starting the REPL. They're
+    /// joined with spaces, so `rand_api 3d6 + 2` works without quotes.
+    pub expressions: Vec<String>,
+}
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Args::default();
+        // Whether the expressions start after "--", so that e.g. "serve" is one
+        let mut only_expressions = false;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--no-color" => parsed.no_color = true,
+                "--format" => {
+                    let format = args.next().ok_or("--format needs a format, e.g. \"--format json\"")?;
+                    parsed.format = Format::parse(&format)?;
+                }
+                option if option.starts_with("--format=") => parsed.format = Format::parse(&option["--format=".len()..])?,
+                "--file" => {
+                    let path = args.next().ok_or("--file needs a path, e.g. \"--file rolls.txt\"")?;
+                    parsed.file = Some(PathBuf::from(path));
+                }
+                option if option.starts_with("--file=") => parsed.file = Some(PathBuf::from(&option["--file=".len()..])),
+                // Everything after "--" is an expression, even if it looks like an option
+                "--" => {
+                    only_expressions = parsed.expressions.is_empty();
+                    parsed.expressions.extend(args.by_ref());
+                }
+                // A single "-" can start an expression, e.g. "-1-1"
+                option if option.starts_with("--") => return Err(format!("unknown option \"{option}\"")),
+                _ => parsed.expressions.push(arg),
+            }
+        }
+        if !only_expressions && parsed.expressions.first().is_some_and(|command| command == "serve") {
+            if parsed.expressions.len() > 2 {
+                return Err("serve takes at most one address, e.g. \"serve 127.0.0.1:8080\"".to_owned());
+            }
+            let address = parsed.expressions.get(1).map_or(DEFAULT_SERVE_ADDRESS, String::as_str);
+            parsed.serve = Some(address.to_owned());
+            parsed.expressions.clear();
+        }
+        if parsed.file.is_some() && (parsed.serve.is_some() || !parsed.expressions.is_empty()) {
+            return Err("--file can't be used with expressions or serve".to_owned());
+        }
+        Ok(parsed)
+    }
+}
+
+/// Evaluate one line, or show the help if it's e.g. "help dice", printing
+/// its output. Returns whether it was evaluated without errors.
+pub fn run_line(session: &mut Session, line: &str, format: Format, renderer: &Renderer) -> bool {
+    if let Some(query) = line.trim().strip_prefix("help") {
+        if query.is_empty() || query.starts_with(' ') {
+            return match help(query) {
+                Ok(text) => {
+                    println!("{text}");
+                    true
+                }
+                Err(e) => {
+                    eprintln!("{}", renderer.render_error(&e));
+                    false
+                }
+            };
+        }
+    }
+    match format {
+        Format::Text => {
+            if let Err(e) = validate(line) {
+                eprintln!("{}", renderer.render_error(&e.to_string()));
+                return false;
+            }
+            let evaluation = session.evaluate(line);
+            // Errors go to stderr like the parse errors above, so
+            // that only results end up where the output is sent
+            for outcome in &evaluation.outcomes {
+                if outcome.is_error() {
+                    eprintln!("{}", renderer.render_outcome(outcome));
+                } else {
+                    println!("{}", renderer.render_outcome(outcome));
+                }
+            }
+            evaluation.is_ok()
+        }
+        // Parse errors are part of the JSON document, with where they are
+        Format::Json => {
+            let evaluation = session.evaluate(line);
+            println!("{}", evaluation.to_json());
+            evaluation.is_ok()
+        }
+    }
+}
+
+/// Evaluate each line of a file, skipping blank lines and comments (lines
+/// starting with "#"), and carrying on past errors. Each result is shown
+/// with its line number, followed by a summary of the lines that failed.
+/// Macros defined in the file are only kept while it's evaluated.
+/// Returns whether every line was evaluated without errors.
+pub fn run_file(session: &mut Session, path: &Path, format: Format, renderer: &Renderer) -> io::Result<bool> {
+    let contents = fs::read_to_string(path)?;
+    let mut evaluated = 0;
+    let mut failed_lines = vec![];
+    for (i, line) in contents.lines().enumerate() {
+        let line_number = i + 1;
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        evaluated += 1;
+        let evaluation = session.evaluate(line);
+        if !evaluation.is_ok() {
+            failed_lines.push(line_number);
+        }
+        match format {
+            Format::Text => {
+                // Outputs with several lines are lined up after the line number
+                let indent = " ".repeat(line_number.to_string().len() + 2);
+                let output = renderer.render(&evaluation).replace('\n', &format!("\n{indent}"));
+                println!("{line_number}: {output}");
+            }
+            Format::Json => println!("{}", evaluation.to_json_with(&[("line", line_number.to_string())])),
+        }
+    }
+
+    if failed_lines.is_empty() {
+        eprintln!("Evaluated {evaluated} lines without errors");
+    } else {
+        let lines: Vec<String> = failed_lines.iter().map(ToString::to_string).collect();
+        let label = if lines.len() == 1 { "line" } else { "lines" };
+        eprintln!("{} of {evaluated} lines failed ({label} {})", lines.len(), lines.join(", "));
+    }
+    Ok(failed_lines.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{Args, Format, DEFAULT_SERVE_ADDRESS};
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn expressions(expressions: &[&str]) -> Args {
+        Args {
+            expressions: expressions.iter().map(|expression| expression.to_string()).collect(),
+            ..Args::default()
+        }
+    }
+
+    #[test]
+    fn valid_args() {
+        let cases: Vec<(&[&str], Args)> = vec![
+            (&[], Args::default()),
+            (&["3d6", "+", "2"], expressions(&["3d6", "+", "2"])),
+            (&["-1-1"], expressions(&["-1-1"])),
+            (&["--", "--no-color", "-1"], expressions(&["--no-color", "-1"])),
+            (&["--", "serve"], expressions(&["serve"])),
+            (&["d20", "--", "--format"], expressions(&["d20", "--format"])),
+            (&["--no-color", "coin"], Args { no_color: true, ..expressions(&["coin"]) }),
+            (&["--format", "json", "d20"], Args { format: Format::Json, ..expressions(&["d20"]) }),
+            (&["--format=json", "d20"], Args { format: Format::Json, ..expressions(&["d20"]) }),
+            (&["--format=text"], Args::default()),
+            (&["serve"], Args { serve: Some(DEFAULT_SERVE_ADDRESS.to_owned()), ..Args::default() }),
+            (&["serve", "0.0.0.0:80"], Args { serve: Some("0.0.0.0:80".to_owned()), ..Args::default() }),
+            (&["--file", "rolls.txt"], Args { file: Some(PathBuf::from("rolls.txt")), ..Args::default() }),
+            (
+                &["--file=rolls.txt", "--format=json"],
+                Args { file: Some(PathBuf::from("rolls.txt")), format: Format::Json, ..Args::default() },
+            ),
+        ];
+        for (args, expected) in cases {
+            assert_eq!(parse(args), Ok(expected), "{args:?}");
+        }
+    }
+
+    #[test]
+    fn invalid_args() {
+        let cases: &[&[&str]] = &[
+            &["--format"],
+            &["--format="],
+            &["--format=xml"],
+            &["--format", "xml"],
+            &["--colour"],
+            &["--file"],
+            &["serve", "127.0.0.1:80", "127.0.0.1:81"],
+            &["--file", "rolls.txt", "3d6"],
+            &["serve", "--file", "rolls.txt"],
+            &["--file=rolls.txt", "serve"],
+        ];
+        for args in cases {
+            assert!(parse(args).is_err(), "{args:?}");
+        }
+    }
+}
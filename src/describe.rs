@@ -0,0 +1,216 @@
+//! Working out what an expression could evaluate to without
+//! evaluating it, e.g. "3..18, avg 10.5" for "3d6"
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{
+    dice::{
+        advantage::AdvantageStatus,
+        atom::DiceExpressionAtom,
+        keepdrop::KeepOrDrop,
+        roll::roll_dice,
+    },
+    evaluate::MAX_DICE,
+    interval::Interval,
+    value::IntValue,
+    Expression, Session,
+};
+
+/// How many times dice with modifiers (e.g. "4d6dl1") are
+/// rolled to estimate their average
+const SIMULATED_ROLLS: usize = 2_000;
+
+/// The lowest, highest, and average total of part of an expression,
+/// and whether the average is exact or estimated by rolling
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Stats {
+    min: i64,
+    max: i64,
+    average: f64,
+    exact: bool,
+}
+
+impl Stats {
+    fn constant(value: i64) -> Self {
+        Stats {
+            min: value,
+            max: value,
+            average: value as f64,
+            exact: true,
+        }
+    }
+
+    /// `None` if either end is out of range once negated, e.g. `i64::MIN`
+    fn negated(self) -> Option<Self> {
+        Some(Stats {
+            min: self.max.checked_neg()?,
+            max: self.min.checked_neg()?,
+            average: -self.average,
+            exact: self.exact,
+        })
+    }
+
+    /// `None` if either end of the total is out of range
+    fn add(self, other: Stats) -> Option<Self> {
+        Some(Stats {
+            min: self.min.checked_add(other.min)?,
+            max: self.max.checked_add(other.max)?,
+            average: self.average + other.average,
+            exact: self.exact && other.exact,
+        })
+    }
+}
+
+pub fn describe_expression(expression: &Expression, session: &Session) -> Option<String> {
+    match expression {
+        Expression::CoinFlip => Some("Heads or Tails".to_owned()),
+        Expression::IntRange { min, max, interval, step } => {
+            let step_size = step.unwrap_or(1);
+            let (min, max) = interval.int_bounds(resolve(min, session)?, resolve(max, session)?, step_size)?;
+            let step = step.map(|step| format!(" step {step}")).unwrap_or_default();
+            Some(format!("{min}..{max}{step}, avg {}", format_average((min as f64 + max as f64) / 2.0)))
+        }
+        Expression::FloatRange { min, max, interval, decimals, step } => {
+            let decimals = *decimals as usize;
+            let (low, high) = (format!("{min:.decimals$}"), format!("{max:.decimals$}"));
+            // The ends that are left out are shown with interval notation, e.g. "[0, 1)"
+            let range = match interval {
+                Interval::Closed => format!("{low}..{high}"),
+                _ => {
+                    let open = if interval.includes_min() { "[" } else { "(" };
+                    let close = if interval.includes_max() { "]" } else { ")" };
+                    format!("{open}{low}, {high}{close}")
+                }
+            };
+            let step = step.map(|step| format!(" step {step:.decimals$}")).unwrap_or_default();
+            Some(format!("{range}{step}, avg {}", format_average((min + max) / 2.0)))
+        }
+        Expression::Distribution { distribution, .. } => {
+            distribution.check().ok()?;
+            Some(format!(
+                "avg {}, sd {}",
+                format_average(distribution.mean()),
+                format_average(distribution.std_dev())
+            ))
+        }
+        Expression::DiceExpression(atoms) => {
+            let stats = atoms_stats(atoms, session)?;
+            if stats.min == stats.max {
+                return Some(format!("= {}", stats.min));
+            }
+            let approximately = if stats.exact { "" } else { "≈ " };
+            Some(format!(
+                "{}..{}, avg {approximately}{}",
+                stats.min,
+                stats.max,
+                format_average(stats.average)
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Show at most one decimal place, and none for whole numbers
+fn format_average(average: f64) -> String {
+    let rounded = format!("{average:.1}");
+    rounded.strip_suffix(".0").map(str::to_owned).unwrap_or(rounded)
+}
+
+/// The value of an integer, if it can be known without rolling any dice
+fn resolve(value: &IntValue, session: &Session) -> Option<i64> {
+    match value {
+        IntValue::Literal(value) => Some(*value),
+        IntValue::Variable(name) => session.variable(name),
+        IntValue::Expression(atoms) => {
+            let stats = atoms_stats(atoms, session)?;
+            (stats.min == stats.max).then_some(stats.min)
+        }
+    }
+}
+
+fn atoms_stats(atoms: &[DiceExpressionAtom], session: &Session) -> Option<Stats> {
+    atoms
+        .iter()
+        .map(|atom| atom_stats(atom, session))
+        .try_fold(Stats::constant(0), |total, stats| total.add(stats?))
+}
+
+fn atom_stats(atom: &DiceExpressionAtom, session: &Session) -> Option<Stats> {
+    match atom {
+        DiceExpressionAtom::Constant(value) => Some(Stats::constant(*value)),
+        DiceExpressionAtom::Variable { name, subtracted } => {
+            let stats = Stats::constant(session.variable(name)?);
+            if *subtracted { stats.negated() } else { Some(stats) }
+        }
+        DiceExpressionAtom::Roll {
+            number_of_dice,
+            number_of_sides,
+            advantage_status,
+            keep_drop,
+            reroll,
+            subtracted,
+        } => {
+            let number_of_dice = resolve(number_of_dice, session)?;
+            // Too many dice can't be rolled (see evaluate), and simulating them would hang the hinter
+            if *number_of_sides == 0 || !(0..=MAX_DICE).contains(&number_of_dice) {
+                return None;
+            }
+            let number_of_dice = number_of_dice as usize;
+            // Keeping or dropping dice only changes how many dice count
+            // towards the lowest and highest totals, not the sides
+            let kept = keep_drop.iter().fold(number_of_dice, |kept, kd| match kd.keep_or_drop {
+                KeepOrDrop::Keep => kept.min(kd.amount as usize),
+                KeepOrDrop::Drop => kept.saturating_sub(kd.amount as usize),
+            }) as i64;
+            let sides = *number_of_sides as i64;
+            let has_modifiers =
+                !keep_drop.is_empty() || reroll.is_some() || *advantage_status != AdvantageStatus::None;
+            let (average, exact) = if has_modifiers {
+                // The average with modifiers is estimated by rolling.
+                // The seed is fixed so the estimate doesn't change as you type.
+                let mut rng = StdRng::seed_from_u64(0);
+                let total: i64 = (0..SIMULATED_ROLLS)
+                    .map(|_| {
+                        roll_dice(number_of_dice, *number_of_sides, advantage_status, keep_drop, *reroll, &mut rng)
+                            .iter()
+                            .filter(|die| die.kept)
+                            .map(|die| die.value as i64)
+                            .sum::<i64>()
+                    })
+                    .sum();
+                (total as f64 / SIMULATED_ROLLS as f64, false)
+            } else {
+                (kept as f64 * (sides as f64 + 1.0) / 2.0, true)
+            };
+            let stats = Stats {
+                min: kept,
+                max: kept.checked_mul(sides)?,
+                average,
+                exact,
+            };
+            if *subtracted { stats.negated() } else { Some(stats) }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Session;
+
+    #[test]
+    fn describe_dice() {
+        let mut session = Session::new();
+        assert_eq!(session.describe("3d6"), Some("3..18, avg 10.5".to_owned()));
+        assert_eq!(session.describe("d20 - 1d4 + 2"), Some("-1..21, avg 10".to_owned()));
+        assert_eq!(session.describe("1-maxhp"), None);
+        session.eval("maxhp = 12");
+        assert_eq!(session.describe("1-maxhp; coin"), Some("1..12, avg 6.5; Heads or Tails".to_owned()));
+        assert!(session.describe("4d6dl1").unwrap().starts_with("3..18, avg ≈ 12."));
+        assert_eq!(session.describe("shuffle a, b"), None);
+        assert_eq!(session.describe("3d6 ?"), None);
+        assert_eq!(session.describe("2000000d6dl1"), None);
+        assert_eq!(session.describe("9223372036854775807d6"), None);
+        assert_eq!(session.describe("9223372036854775807 + 9223372036854775807"), None);
+        assert_eq!(session.describe("d4 - 9223372036854775807 - 9"), None);
+    }
+}
@@ -1,6 +1,20 @@
+use std::fmt::{self, Display};
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AdvantageStatus {
     Advantage,
     Disadvantage,
     None
-}
\ No newline at end of file
+}
+
+impl Display for AdvantageStatus {
+    /// Write the shortest form of the modifier, e.g. the "a" in "1d20a"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdvantageStatus::Advantage => f.write_str("a"),
+            AdvantageStatus::Disadvantage => f.write_str("d"),
+            AdvantageStatus::None => Ok(()),
+        }
+    }
+}
@@ -1,20 +1,34 @@
+use std::fmt::{self, Display};
+
+use crate::value::IntValue;
+
 use super::{advantage::AdvantageStatus, keepdrop::KeepDrop, roll_modifier::RollModifier};
 
 /// A dice roll atom is the smallest unit in a dice roll expression.
 /// For example, in "3d8 + 2d6r1 + 8", the atoms are "3d8", "2d6r1", and "8"
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DiceExpressionAtom {
     /// An signed integer constant,
     /// e.g. the "-1" in "d20 - 1"
     /// A.k.a. a "modifier", but that
     /// term was avoided to evade confusion
     /// with roll modifiers (e.g. advantage, rerolls, etc)
-    Constant(i8),
+    Constant(i64),
+
+    /// The value of a variable set earlier in the session,
+    /// e.g. the "str" in "d20 + str"
+    Variable {
+        name: String,
+        /// Whether this atom is subtracted, e.g. in
+        /// "d20 - cover", the cover is subtracted
+        subtracted: bool,
+    },
 
     Roll {
         /// How many dice are rolled,
-        /// e.g. 3 in "3d6"
-        number_of_dice: u8,
+        /// e.g. 3 in "3d6" or n+1 in "(n+1)d6"
+        number_of_dice: IntValue,
         /// How many sides each dice has,
         /// e.g. 6 in "3d6"
         number_of_sides: u8,
@@ -22,9 +36,9 @@ pub enum DiceExpressionAtom {
         /// disadvantage, or neither
         advantage_status: AdvantageStatus,
         /// An optional modifier that lets you drop the
-        /// highest or lowest <some number> rolls, or drop
+        /// highest or lowest `<some number>` rolls, or drop
         /// everything and only keep the highest/lowest
-        /// <some number> rolls
+        /// `<some number>` rolls
         keep_drop: Vec<KeepDrop>,
         /// An optional modifier to reroll all dice
         /// (once) that land on the given number or lower.
@@ -38,7 +52,11 @@ pub enum DiceExpressionAtom {
 }
 
 impl DiceExpressionAtom {
-    pub fn new(number_of_dice: u8, number_of_sides: u8, subtracted: bool, modifiers: Vec<RollModifier>) -> Self {
+    /// A roll with the given modifiers, the same way the parser builds
+    /// one, e.g. 4, 6, and `[RollModifier::KeepDrop(KeepDrop::drop_lowest(1))]`
+    /// for "4d6dl1". A later advantage, disadvantage or reroll
+    /// replaces an earlier one, and keeping or dropping is done in order.
+    pub fn new(number_of_dice: IntValue, number_of_sides: u8, subtracted: bool, modifiers: Vec<RollModifier>) -> Self {
         let (advantage_status, reroll, drop_keep) = {
             let mut advantage_status = AdvantageStatus::None;
             let mut reroll = None;
@@ -71,4 +89,101 @@ impl DiceExpressionAtom {
             subtracted,
         }
     }
-}
\ No newline at end of file
+}
+
+impl DiceExpressionAtom {
+    /// Whether this atom takes away from the total,
+    /// e.g. the "-1" and "-d4" in "d20 - 1 - d4"
+    pub fn is_negative(&self) -> bool {
+        match self {
+            DiceExpressionAtom::Constant(value) => *value < 0,
+            DiceExpressionAtom::Variable { subtracted, .. } | DiceExpressionAtom::Roll { subtracted, .. } => {
+                *subtracted
+            }
+        }
+    }
+
+    /// The same atom taking away from the total instead of adding
+    /// to it or vice versa, e.g. "-d4" for "d4". That's one atom,
+    /// except for `i64::MIN`, whose negation doesn't fit in an i64
+    /// and so is split into `i64::MAX` and 1.
+    pub(crate) fn negated(self) -> Vec<Self> {
+        let atom = match self {
+            DiceExpressionAtom::Constant(i64::MIN) => {
+                return vec![DiceExpressionAtom::Constant(i64::MAX), DiceExpressionAtom::Constant(1)]
+            }
+            DiceExpressionAtom::Constant(value) => DiceExpressionAtom::Constant(-value),
+            DiceExpressionAtom::Variable { name, subtracted } => DiceExpressionAtom::Variable {
+                name,
+                subtracted: !subtracted,
+            },
+            DiceExpressionAtom::Roll {
+                number_of_dice,
+                number_of_sides,
+                advantage_status,
+                keep_drop,
+                reroll,
+                subtracted,
+            } => DiceExpressionAtom::Roll {
+                number_of_dice,
+                number_of_sides,
+                advantage_status,
+                keep_drop,
+                reroll,
+                subtracted: !subtracted,
+            },
+        };
+        vec![atom]
+    }
+
+    /// Write the atom without its sign, e.g. "6d6r2dl2" for "-6d6r2dl2"
+    fn write_unsigned(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiceExpressionAtom::Constant(value) => write!(f, "{}", value.unsigned_abs()),
+            DiceExpressionAtom::Variable { name, .. } => f.write_str(name),
+            DiceExpressionAtom::Roll {
+                number_of_dice,
+                number_of_sides,
+                advantage_status,
+                keep_drop,
+                reroll,
+                ..
+            } => {
+                write!(f, "{number_of_dice}d{number_of_sides}")?;
+                if let Some(reroll) = reroll {
+                    write!(f, "r{reroll}")?;
+                }
+                for kd in keep_drop {
+                    write!(f, "{kd}")?;
+                }
+                write!(f, "{advantage_status}")
+            }
+        }
+    }
+}
+
+/// Write the atom in the same form for however it was typed,
+/// with the number of dice and every modifier written out,
+/// e.g. "-6d6r2dl2dh2" or "1d20a" (for "d20adv")
+impl Display for DiceExpressionAtom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_negative() {
+            f.write_str("-")?;
+        }
+        self.write_unsigned(f)
+    }
+}
+
+/// Write atoms with a sign between each of them, e.g. "-6d6 + 1d20a - 5"
+pub fn write_atoms(f: &mut fmt::Formatter<'_>, atoms: &[DiceExpressionAtom]) -> fmt::Result {
+    for (i, atom) in atoms.iter().enumerate() {
+        match (i, atom.is_negative()) {
+            (0, _) => write!(f, "{atom}")?,
+            (_, negative) => {
+                f.write_str(if negative { " - " } else { " + " })?;
+                atom.write_unsigned(f)?;
+            }
+        }
+    }
+    Ok(())
+}
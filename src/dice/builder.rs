@@ -0,0 +1,231 @@
+//! Building dice expressions in code rather than by parsing text,
+//! e.g. `Dice::new(4, 6).drop_lowest(1) + 2` for "4d6dl1 + 2"
+
+use std::ops::{Add, Sub};
+
+use crate::{value::IntValue, Expression};
+
+use super::{atom::DiceExpressionAtom, keepdrop::KeepDrop, roll_modifier::RollModifier};
+
+/// A roll with its modifiers, e.g. `Dice::new(4, 6).drop_lowest(1).reroll_below(1)`
+/// for "4d6r1dl1". It becomes the same atom the parser makes for that text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dice {
+    number_of_dice: IntValue,
+    number_of_sides: u8,
+    modifiers: Vec<RollModifier>,
+}
+
+impl Dice {
+    /// Roll `number_of_dice` dice with `number_of_sides` sides, e.g. `Dice::new(3, 6)`
+    /// for "3d6". The number of dice can also be the total of a dice sum,
+    /// e.g. `Dice::new(DiceSum::variable("n") + 1, 8)` for "(n + 1)d8".
+    pub fn new(number_of_dice: impl Into<IntValue>, number_of_sides: u8) -> Self {
+        Dice {
+            number_of_dice: number_of_dice.into(),
+            number_of_sides,
+            modifiers: vec![],
+        }
+    }
+
+    /// A single die, e.g. `Dice::d(20)` for "d20"
+    pub fn d(number_of_sides: u8) -> Self {
+        Dice::new(1, number_of_sides)
+    }
+
+    /// "khN"
+    pub fn keep_highest(self, amount: u8) -> Self {
+        self.with(RollModifier::KeepDrop(KeepDrop::keep_highest(amount)))
+    }
+
+    /// "klN"
+    pub fn keep_lowest(self, amount: u8) -> Self {
+        self.with(RollModifier::KeepDrop(KeepDrop::keep_lowest(amount)))
+    }
+
+    /// "dhN"
+    pub fn drop_highest(self, amount: u8) -> Self {
+        self.with(RollModifier::KeepDrop(KeepDrop::drop_highest(amount)))
+    }
+
+    /// "dlN"
+    pub fn drop_lowest(self, amount: u8) -> Self {
+        self.with(RollModifier::KeepDrop(KeepDrop::drop_lowest(amount)))
+    }
+
+    /// "rN", which rerolls (once) each die that lands on `value` or lower
+    pub fn reroll_below(self, value: u8) -> Self {
+        self.with(RollModifier::Reroll(value))
+    }
+
+    /// "a", which rolls the dice twice and keeps the higher total
+    pub fn advantage(self) -> Self {
+        self.with(RollModifier::Advantage)
+    }
+
+    /// "d", which rolls the dice twice and keeps the lower total
+    pub fn disadvantage(self) -> Self {
+        self.with(RollModifier::Disadvantage)
+    }
+
+    fn with(mut self, modifier: RollModifier) -> Self {
+        self.modifiers.push(modifier);
+        self
+    }
+
+    pub fn plus(self, term: impl Into<DiceSum>) -> DiceSum {
+        DiceSum::from(self).plus(term)
+    }
+
+    pub fn minus(self, term: impl Into<DiceSum>) -> DiceSum {
+        DiceSum::from(self).minus(term)
+    }
+}
+
+impl From<Dice> for DiceExpressionAtom {
+    fn from(dice: Dice) -> Self {
+        DiceExpressionAtom::new(dice.number_of_dice, dice.number_of_sides, false, dice.modifiers)
+    }
+}
+
+/// Rolls, constants and variables added together, e.g.
+/// `Dice::d(20) + 5 - Dice::d(4)` for "d20 + 5 - d4"
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiceSum {
+    atoms: Vec<DiceExpressionAtom>,
+}
+
+impl DiceSum {
+    /// The value of a variable on its own, e.g. "n"
+    pub fn variable(name: &str) -> Self {
+        variable(name, false)
+    }
+
+    pub fn plus(mut self, term: impl Into<DiceSum>) -> Self {
+        self.atoms.extend(term.into().atoms);
+        self
+    }
+
+    pub fn minus(mut self, term: impl Into<DiceSum>) -> Self {
+        self.atoms.extend(term.into().atoms.into_iter().flat_map(DiceExpressionAtom::negated));
+        self
+    }
+
+    /// Add the value of a variable, e.g. `.plus_variable("str")` for "+ str"
+    pub fn plus_variable(self, name: &str) -> Self {
+        self.plus(variable(name, false))
+    }
+
+    /// Subtract the value of a variable, e.g. `.minus_variable("cover")` for "- cover"
+    pub fn minus_variable(self, name: &str) -> Self {
+        self.plus(variable(name, true))
+    }
+
+    pub fn atoms(&self) -> &[DiceExpressionAtom] {
+        &self.atoms
+    }
+}
+
+fn variable(name: &str, subtracted: bool) -> DiceSum {
+    DiceSum {
+        atoms: vec![DiceExpressionAtom::Variable {
+            name: name.to_owned(),
+            subtracted,
+        }],
+    }
+}
+
+impl From<Dice> for DiceSum {
+    fn from(dice: Dice) -> Self {
+        DiceSum { atoms: vec![dice.into()] }
+    }
+}
+
+impl From<i64> for DiceSum {
+    fn from(value: i64) -> Self {
+        DiceSum {
+            atoms: vec![DiceExpressionAtom::Constant(value)],
+        }
+    }
+}
+
+impl From<DiceSum> for Expression {
+    fn from(sum: DiceSum) -> Self {
+        Expression::DiceExpression(sum.atoms)
+    }
+}
+
+impl From<Dice> for Expression {
+    fn from(dice: Dice) -> Self {
+        DiceSum::from(dice).into()
+    }
+}
+
+/// The total of a dice sum can be the number of dice in another
+/// roll, e.g. `Dice::new(Dice::d(4) + 1, 6)` for "(1d4 + 1)d6"
+impl From<DiceSum> for IntValue {
+    fn from(sum: DiceSum) -> Self {
+        IntValue::Expression(sum.atoms)
+    }
+}
+
+impl<T: Into<DiceSum>> Add<T> for Dice {
+    type Output = DiceSum;
+
+    fn add(self, term: T) -> DiceSum {
+        self.plus(term)
+    }
+}
+
+impl<T: Into<DiceSum>> Sub<T> for Dice {
+    type Output = DiceSum;
+
+    fn sub(self, term: T) -> DiceSum {
+        self.minus(term)
+    }
+}
+
+impl<T: Into<DiceSum>> Add<T> for DiceSum {
+    type Output = DiceSum;
+
+    fn add(self, term: T) -> DiceSum {
+        self.plus(term)
+    }
+}
+
+impl<T: Into<DiceSum>> Sub<T> for DiceSum {
+    type Output = DiceSum;
+
+    fn sub(self, term: T) -> DiceSum {
+        self.minus(term)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dice::builder::{Dice, DiceSum},
+        parse, Expression,
+    };
+
+    #[test]
+    fn same_as_parser() {
+        let built: Expression = Dice::new(4, 6).drop_lowest(1).reroll_below(1).advantage().into();
+        assert_eq!(built, parse("4d6dl1r1a").unwrap());
+
+        let built: Expression = (Dice::d(20) + 5 - Dice::new(2, 4).keep_highest(1)).plus_variable("str").into();
+        assert_eq!(built, parse("d20 + 5 - 2d4kh1 + str").unwrap());
+
+        let built: Expression = Dice::new(6, 6).minus(Dice::d(8).disadvantage() - 3).into();
+        assert_eq!(built, parse("6d6 - 1d8d + 3").unwrap());
+
+        let built: Expression = Dice::new(DiceSum::from(Dice::d(4)).plus_variable("level"), 6).into();
+        assert_eq!(built, parse("(1d4 + level)d6").unwrap());
+
+        let built: Expression = Dice::new(DiceSum::variable("n") + 1, 8).into();
+        assert_eq!(built, parse("(n+1)d8").unwrap());
+
+        let built: Expression = Dice::d(4).minus(i64::MIN).into();
+        assert_eq!(built, parse("d4 + 9223372036854775807 + 1").unwrap());
+    }
+}
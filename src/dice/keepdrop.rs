@@ -1,11 +1,15 @@
 //! Utilities for keeping or dropping the highest or lowest dice rolls in a set of dice rolls.
 //! E.g. "kh3" = keep highest 3 rolls and discard the rest
 
+use std::fmt::{self, Display};
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum KeepOrDrop {
     Keep,
     Drop,
 }
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 
 pub enum HighestOrLowest {
@@ -14,8 +18,46 @@ pub enum HighestOrLowest {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct KeepDrop {
     pub keep_or_drop: KeepOrDrop,
     pub amount: u8,
     pub highest_or_lowest: HighestOrLowest,
 }
+
+impl KeepDrop {
+    /// e.g. "kh3"
+    pub fn keep_highest(amount: u8) -> Self {
+        KeepDrop { keep_or_drop: KeepOrDrop::Keep, amount, highest_or_lowest: HighestOrLowest::Highest }
+    }
+
+    /// e.g. "kl1"
+    pub fn keep_lowest(amount: u8) -> Self {
+        KeepDrop { keep_or_drop: KeepOrDrop::Keep, amount, highest_or_lowest: HighestOrLowest::Lowest }
+    }
+
+    /// e.g. "dh1"
+    pub fn drop_highest(amount: u8) -> Self {
+        KeepDrop { keep_or_drop: KeepOrDrop::Drop, amount, highest_or_lowest: HighestOrLowest::Highest }
+    }
+
+    /// e.g. "dl1"
+    pub fn drop_lowest(amount: u8) -> Self {
+        KeepDrop { keep_or_drop: KeepOrDrop::Drop, amount, highest_or_lowest: HighestOrLowest::Lowest }
+    }
+}
+
+impl Display for KeepDrop {
+    /// Write the modifier with both letters, e.g. "kh3" rather than "k3"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keep_or_drop = match self.keep_or_drop {
+            KeepOrDrop::Keep => "k",
+            KeepOrDrop::Drop => "d",
+        };
+        let highest_or_lowest = match self.highest_or_lowest {
+            HighestOrLowest::Highest => "h",
+            HighestOrLowest::Lowest => "l",
+        };
+        write!(f, "{keep_or_drop}{highest_or_lowest}{}", self.amount)
+    }
+}
@@ -1,4 +1,6 @@
+pub mod builder;
 pub mod keepdrop;
 pub mod advantage;
 pub mod atom;
-pub mod roll_modifier;
\ No newline at end of file
+pub mod roll_modifier;
+pub mod roll;
\ No newline at end of file
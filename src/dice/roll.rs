@@ -0,0 +1,205 @@
+//! Rolling the dice in a dice expression atom, e.g. "4d6dl1"
+
+use rand::Rng;
+
+use super::{
+    advantage::AdvantageStatus,
+    keepdrop::{HighestOrLowest, KeepDrop, KeepOrDrop},
+};
+
+/// The result of rolling a single die
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DieRoll {
+    pub value: u8,
+    /// False if this die was dropped (e.g. by "dl1"), or was part of the
+    /// losing set of dice when rolling with advantage or disadvantage
+    pub kept: bool,
+}
+
+/// The result of evaluating a single dice expression atom
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AtomResult {
+    Constant(i64),
+    Variable {
+        name: String,
+        value: i64,
+        subtracted: bool,
+    },
+    Roll {
+        /// How many dice were rolled, not counting the second
+        /// set rolled for advantage or disadvantage
+        number_of_dice: usize,
+        number_of_sides: u8,
+        /// Every die that was rolled, including dropped dice
+        dice: Vec<DieRoll>,
+        subtracted: bool,
+    },
+}
+
+impl AtomResult {
+    /// What this atom adds to the total of the expression (negative if
+    /// it is subtracted), or `None` if that doesn't fit in an i64, e.g.
+    /// when subtracting a variable that's set to `i64::MIN`
+    pub fn total(&self) -> Option<i64> {
+        i64::try_from(self.wide_total()).ok()
+    }
+
+    /// The total as an i128, which every atom's total fits in
+    fn wide_total(&self) -> i128 {
+        let (value, subtracted) = match self {
+            AtomResult::Constant(value) => (*value, false),
+            AtomResult::Variable { value, subtracted, .. } => (*value, *subtracted),
+            AtomResult::Roll { dice, subtracted, .. } => (kept_total(dice), *subtracted),
+        };
+        if subtracted {
+            -(value as i128)
+        } else {
+            value as i128
+        }
+    }
+}
+
+/// The total of a whole dice expression, or `None` if it doesn't fit
+/// in an i64. Only the total has to fit, e.g. "-1 - x" is fine when x
+/// is `i64::MIN`.
+pub fn atoms_total(atoms: &[AtomResult]) -> Option<i64> {
+    i64::try_from(atoms.iter().map(AtomResult::wide_total).sum::<i128>()).ok()
+}
+
+fn kept_total(dice: &[DieRoll]) -> i64 {
+    dice.iter().filter(|die| die.kept).map(|die| die.value as i64).sum()
+}
+
+/// Roll `number_of_dice` dice, applying the reroll, keep/drop, and
+/// advantage modifiers in that order. With advantage (or disadvantage),
+/// the whole set of dice is rolled twice and the set with the higher
+/// (or lower) total is kept.
+pub fn roll_dice<R: Rng + ?Sized>(
+    number_of_dice: usize,
+    number_of_sides: u8,
+    advantage_status: &AdvantageStatus,
+    keep_drop: &[KeepDrop],
+    reroll: Option<u8>,
+    rng: &mut R,
+) -> Vec<DieRoll> {
+    let mut roll_set = || {
+        let mut dice: Vec<DieRoll> = (0..number_of_dice)
+            .map(|_| {
+                let mut value = rng.gen_range(1..=number_of_sides);
+                if reroll.is_some_and(|reroll| value <= reroll) {
+                    value = rng.gen_range(1..=number_of_sides);
+                }
+                DieRoll { value, kept: true }
+            })
+            .collect();
+        for kd in keep_drop {
+            apply_keep_drop(&mut dice, kd);
+        }
+        dice
+    };
+
+    match advantage_status {
+        AdvantageStatus::None => roll_set(),
+        AdvantageStatus::Advantage | AdvantageStatus::Disadvantage => {
+            let mut first = roll_set();
+            let mut second = roll_set();
+            let first_wins = match advantage_status {
+                AdvantageStatus::Advantage => kept_total(&first) >= kept_total(&second),
+                _ => kept_total(&first) <= kept_total(&second),
+            };
+            let loser = if first_wins { &mut second } else { &mut first };
+            for die in loser.iter_mut() {
+                die.kept = false;
+            }
+            first.append(&mut second);
+            first
+        }
+    }
+}
+
+/// Mark dice as not kept according to a keep/drop modifier.
+/// Dice that were already dropped are ignored, so e.g. in "6d6dl2dh2"
+/// the highest 2 are dropped from the 4 dice left after dropping the lowest 2
+fn apply_keep_drop(dice: &mut [DieRoll], kd: &KeepDrop) {
+    let mut kept: Vec<&mut DieRoll> = dice.iter_mut().filter(|die| die.kept).collect();
+    // Sort so that the dice to drop are at the start
+    match kd.highest_or_lowest {
+        HighestOrLowest::Lowest => kept.sort_by_key(|die| die.value),
+        HighestOrLowest::Highest => kept.sort_by_key(|die| std::cmp::Reverse(die.value)),
+    }
+    let amount = kd.amount as usize;
+    let number_to_drop = match kd.keep_or_drop {
+        KeepOrDrop::Drop => amount.min(kept.len()),
+        // Keeping the highest n means dropping all but the
+        // highest n, i.e. dropping the lowest (len - n)
+        KeepOrDrop::Keep => kept.len().saturating_sub(amount),
+    };
+    let to_drop = match kd.keep_or_drop {
+        KeepOrDrop::Drop => &mut kept[..number_to_drop],
+        KeepOrDrop::Keep => {
+            let len = kept.len();
+            &mut kept[len - number_to_drop..]
+        }
+    };
+    for die in to_drop {
+        die.kept = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::thread_rng;
+
+    use crate::dice::{
+        advantage::AdvantageStatus,
+        keepdrop::{HighestOrLowest, KeepDrop, KeepOrDrop},
+    };
+
+    use super::{apply_keep_drop, roll_dice, DieRoll};
+
+    fn dice(values: &[u8]) -> Vec<DieRoll> {
+        values.iter().map(|&value| DieRoll { value, kept: true }).collect()
+    }
+
+    fn kept_values(dice: &[DieRoll]) -> Vec<u8> {
+        dice.iter().filter(|die| die.kept).map(|die| die.value).collect()
+    }
+
+    #[test]
+    fn keep_and_drop() {
+        let mut rolled = dice(&[3, 6, 1, 5, 2, 4]);
+        apply_keep_drop(
+            &mut rolled,
+            &KeepDrop {
+                keep_or_drop: KeepOrDrop::Drop,
+                amount: 2,
+                highest_or_lowest: HighestOrLowest::Lowest,
+            },
+        );
+        assert_eq!(kept_values(&rolled), [3, 6, 5, 4]);
+        apply_keep_drop(
+            &mut rolled,
+            &KeepDrop {
+                keep_or_drop: KeepOrDrop::Keep,
+                amount: 1,
+                highest_or_lowest: HighestOrLowest::Lowest,
+            },
+        );
+        assert_eq!(kept_values(&rolled), [3]);
+    }
+
+    #[test]
+    fn advantage_keeps_one_set() {
+        let rolled = roll_dice(2, 6, &AdvantageStatus::Advantage, &[], None, &mut thread_rng());
+        assert_eq!(rolled.len(), 4);
+        assert_eq!(kept_values(&rolled).len(), 2);
+    }
+
+    #[test]
+    fn reroll_within_sides() {
+        let rolled = roll_dice(100, 2, &AdvantageStatus::None, &[], Some(1), &mut thread_rng());
+        assert!(rolled.iter().all(|die| (1..=2).contains(&die.value)));
+    }
+}
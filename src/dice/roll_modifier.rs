@@ -1,7 +1,10 @@
 use super::keepdrop::KeepDrop;
 
-#[derive(Debug)]
+/// Something written after a roll that changes how it's rolled,
+/// e.g. the "r1" and "dl1" in "4d6r1dl1"
+#[derive(Debug, Clone, PartialEq)]
 pub enum RollModifier {
+    /// Reroll dice that land on this number or lower, once
     Reroll(u8),
     Disadvantage,
     Advantage,
@@ -0,0 +1,338 @@
+//! Sampling from non-uniform distributions, e.g. "normal(100, 15)",
+//! for quick estimates and Monte Carlo inputs
+
+use std::{
+    f64::consts::PI,
+    fmt::{self, Display},
+};
+
+use rand::Rng;
+
+/// The largest mean "poisson" takes, so that its samples fit in an i64
+const MAX_POISSON_MEAN: f64 = 1e15;
+
+/// Below this mean, Poisson samples are counted out one event at a
+/// time, and above it they're found by transformed rejection
+const POISSON_REJECTION_MEAN: f64 = 10.0;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Distribution {
+    /// A bell curve, e.g. "normal(100, 15)"
+    Normal { mean: f64, std_dev: f64 },
+    /// A number whose logarithm is normally distributed with the
+    /// given mean and standard deviation, e.g. "lognormal(0, 0.5)"
+    LogNormal { mu: f64, sigma: f64 },
+    /// The time until the next of some events that happen `rate`
+    /// times per unit of time on average, e.g. "exp(0.5)"
+    Exponential { rate: f64 },
+    /// A number from `min` to `max`, most likely to be
+    /// near `mode`, e.g. "tri(1, 5, 10)"
+    Triangular { min: f64, mode: f64, max: f64 },
+    /// A number from 0 to 1, e.g. "beta(2, 5)"
+    Beta { alpha: f64, beta: f64 },
+    /// How many of some events happen, when `mean`
+    /// happen on average, e.g. "poisson(3)"
+    Poisson { mean: f64 },
+}
+
+impl Distribution {
+    /// The distribution with the given name and parameters, e.g. "tri" and
+    /// `[1.0, 5.0, 10.0]`, or `None` if it takes a different number of them
+    pub fn new(name: &str, params: &[f64]) -> Option<Self> {
+        let distribution = match (name, params) {
+            ("normal", &[mean, std_dev]) => Distribution::Normal { mean, std_dev },
+            ("lognormal", &[mu, sigma]) => Distribution::LogNormal { mu, sigma },
+            ("exp" | "exponential", &[rate]) => Distribution::Exponential { rate },
+            ("tri" | "triangular", &[min, mode, max]) => Distribution::Triangular { min, mode, max },
+            ("beta", &[alpha, beta]) => Distribution::Beta { alpha, beta },
+            ("poisson", &[mean]) => Distribution::Poisson { mean },
+            _ => return None,
+        };
+        Some(distribution)
+    }
+
+    /// The name the distribution is written with, e.g. "tri"
+    pub fn name(&self) -> &'static str {
+        match self {
+            Distribution::Normal { .. } => "normal",
+            Distribution::LogNormal { .. } => "lognormal",
+            Distribution::Exponential { .. } => "exp",
+            Distribution::Triangular { .. } => "tri",
+            Distribution::Beta { .. } => "beta",
+            Distribution::Poisson { .. } => "poisson",
+        }
+    }
+
+    /// The parameters in the order they're written, e.g. `[1.0, 5.0, 10.0]` for "tri(1, 5, 10)"
+    pub fn params(&self) -> Vec<f64> {
+        match *self {
+            Distribution::Normal { mean, std_dev } => vec![mean, std_dev],
+            Distribution::LogNormal { mu, sigma } => vec![mu, sigma],
+            Distribution::Exponential { rate } => vec![rate],
+            Distribution::Triangular { min, mode, max } => vec![min, mode, max],
+            Distribution::Beta { alpha, beta } => vec![alpha, beta],
+            Distribution::Poisson { mean } => vec![mean],
+        }
+    }
+
+    /// Whether every sample is a whole number
+    pub fn is_discrete(&self) -> bool {
+        matches!(self, Distribution::Poisson { .. })
+    }
+
+    /// Check that the parameters make sense, e.g. that a standard deviation isn't negative
+    pub fn check(&self) -> Result<(), String> {
+        let name = self.name();
+        if self.params().iter().any(|param| !param.is_finite()) {
+            return Err(format!("{name}'s parameters must be finite"));
+        }
+        match *self {
+            Distribution::Normal { std_dev: spread, .. } | Distribution::LogNormal { sigma: spread, .. }
+                if spread < 0.0 =>
+            {
+                Err(format!("{name}'s standard deviation can't be negative"))
+            }
+            Distribution::Exponential { rate } if rate <= 0.0 => Err(format!("{name}'s rate must be positive")),
+            Distribution::Triangular { min, mode, max } if !(min <= mode && mode <= max && min < max) => Err(format!(
+                "{name} needs min <= mode <= max, with min less than max"
+            )),
+            Distribution::Beta { alpha, beta } if alpha <= 0.0 || beta <= 0.0 => {
+                Err(format!("{name}'s parameters must be positive"))
+            }
+            Distribution::Poisson { mean } if !(0.0..=MAX_POISSON_MEAN).contains(&mean) => {
+                Err(format!("{name}'s mean must be from 0 to {MAX_POISSON_MEAN:e}"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The average of the samples, in the long run
+    pub fn mean(&self) -> f64 {
+        match *self {
+            Distribution::Normal { mean, .. } | Distribution::Poisson { mean } => mean,
+            Distribution::LogNormal { mu, sigma } => (mu + sigma * sigma / 2.0).exp(),
+            Distribution::Exponential { rate } => 1.0 / rate,
+            Distribution::Triangular { min, mode, max } => (min + mode + max) / 3.0,
+            Distribution::Beta { alpha, beta } => alpha / (alpha + beta),
+        }
+    }
+
+    /// How far the samples are from the mean, on average
+    pub fn std_dev(&self) -> f64 {
+        let variance = match *self {
+            Distribution::Normal { std_dev, .. } => std_dev * std_dev,
+            Distribution::LogNormal { mu, sigma } => ((sigma * sigma).exp() - 1.0) * (2.0 * mu + sigma * sigma).exp(),
+            Distribution::Exponential { rate } => 1.0 / (rate * rate),
+            Distribution::Triangular { min, mode, max } => {
+                (min * min + mode * mode + max * max - min * mode - min * max - mode * max) / 18.0
+            }
+            Distribution::Beta { alpha, beta } => {
+                alpha * beta / ((alpha + beta) * (alpha + beta) * (alpha + beta + 1.0))
+            }
+            Distribution::Poisson { mean } => mean,
+        };
+        variance.sqrt()
+    }
+
+    /// A random sample, or an error if the parameters don't make sense (see
+    /// `check`) or the sample is too large for an f64, e.g. for "lognormal(1000, 1)"
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Result<f64, String> {
+        self.check()?;
+        let sample = match *self {
+            Distribution::Normal { mean, std_dev } => mean + std_dev * standard_normal(rng),
+            Distribution::LogNormal { mu, sigma } => (mu + sigma * standard_normal(rng)).exp(),
+            Distribution::Exponential { rate } => -open_unit(rng).ln() / rate,
+            Distribution::Triangular { min, mode, max } => {
+                // Invert the cumulative distribution, which is
+                // a parabola on either side of the mode
+                let u: f64 = rng.gen();
+                if u < (mode - min) / (max - min) {
+                    min + (u * (max - min) * (mode - min)).sqrt()
+                } else {
+                    max - ((1.0 - u) * (max - min) * (max - mode)).sqrt()
+                }
+            }
+            Distribution::Beta { alpha, beta } => {
+                // X / (X + Y) for gamma distributed X and Y, worked out
+                // from their logarithms so that tiny shapes can't give 0 / 0
+                let x = ln_gamma_sample(alpha, rng);
+                let y = ln_gamma_sample(beta, rng);
+                1.0 / (1.0 + (y - x).exp())
+            }
+            Distribution::Poisson { mean } if mean < POISSON_REJECTION_MEAN => poisson_by_counting(mean, rng),
+            Distribution::Poisson { mean } => poisson_by_rejection(mean, rng),
+        };
+        if !sample.is_finite() {
+            return Err(format!("{}'s sample is too large", self.name()));
+        }
+        Ok(sample)
+    }
+}
+
+/// Write the distribution the way it's typed, e.g. "tri(1, 5, 10)". A precision
+/// (e.g. `{:.2}`) is the number of decimal places each parameter is written with.
+impl Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}(", self.name())?;
+        for (i, param) in self.params().iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            match f.precision() {
+                Some(decimals) => write!(f, "{param:.decimals$}")?,
+                None => write!(f, "{param}")?,
+            }
+        }
+        f.write_str(")")
+    }
+}
+
+/// A random number in (0, 1], which can be passed to `ln`
+fn open_unit<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    1.0 - rng.gen::<f64>()
+}
+
+/// A normally distributed number with a mean of 0 and a
+/// standard deviation of 1, by the Box-Muller transform
+fn standard_normal<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    let radius = (-2.0 * open_unit(rng).ln()).sqrt();
+    let angle = 2.0 * PI * rng.gen::<f64>();
+    radius * angle.cos()
+}
+
+/// The logarithm of a gamma distributed number with the given shape (and a scale
+/// of 1), by Marsaglia and Tsang's method. Shapes below 1 are boosted by 1, and
+/// the sample scaled back down, which is why the logarithm is returned.
+fn ln_gamma_sample<R: Rng + ?Sized>(shape: f64, rng: &mut R) -> f64 {
+    if shape < 1.0 {
+        return ln_gamma_sample(shape + 1.0, rng) + open_unit(rng).ln() / shape;
+    }
+    let d = shape - 1.0 / 3.0;
+    let c = 1.0 / (9.0 * d).sqrt();
+    loop {
+        let z = standard_normal(rng);
+        let v = (1.0 + c * z).powi(3);
+        if v <= 0.0 {
+            continue;
+        }
+        if open_unit(rng).ln() < z * z / 2.0 + d - d * v + d * v.ln() {
+            return (d * v).ln();
+        }
+    }
+}
+
+/// A Poisson sample for a small mean, by counting how many uniform
+/// numbers can be multiplied together before they drop below e^-mean
+fn poisson_by_counting<R: Rng + ?Sized>(mean: f64, rng: &mut R) -> f64 {
+    let limit = (-mean).exp();
+    let mut product = open_unit(rng);
+    let mut count = 0.0;
+    while product > limit {
+        product *= open_unit(rng);
+        count += 1.0;
+    }
+    count
+}
+
+/// A Poisson sample for a larger mean, by Hörmann's transformed rejection
+/// with squeeze (PTRS), which takes about the same time for any mean
+fn poisson_by_rejection<R: Rng + ?Sized>(mean: f64, rng: &mut R) -> f64 {
+    let ln_mean = mean.ln();
+    let b = 0.931 + 2.53 * mean.sqrt();
+    let a = -0.059 + 0.02483 * b;
+    let inverse_alpha = 1.1239 + 1.1328 / (b - 3.4);
+    let v_r = 0.9277 - 3.6224 / (b - 2.0);
+    loop {
+        let u = rng.gen::<f64>() - 0.5;
+        let v: f64 = rng.gen();
+        let us = 0.5 - u.abs();
+        let k = ((2.0 * a / us + b) * u + mean + 0.43).floor();
+        if us >= 0.07 && v <= v_r {
+            return k;
+        }
+        if k < 0.0 || (us < 0.013 && v > us) {
+            continue;
+        }
+        if (v * inverse_alpha / (a / (us * us) + b)).ln() <= -mean + k * ln_mean - ln_factorial(k) {
+            return k;
+        }
+    }
+}
+
+/// ln(n!), exactly for small n and by Stirling's series otherwise
+fn ln_factorial(n: f64) -> f64 {
+    if n < 10.0 {
+        return (2..=n as u32).map(|i| (i as f64).ln()).sum();
+    }
+    let n2 = n * n;
+    n * n.ln() - n + (2.0 * PI * n).ln() / 2.0 + 1.0 / (12.0 * n) - 1.0 / (360.0 * n * n2)
+        + 1.0 / (1260.0 * n2 * n2 * n)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::distribution::{ln_factorial, Distribution};
+
+    #[test]
+    fn samples_match_mean_and_std_dev() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let distributions = [
+            Distribution::Normal { mean: 100.0, std_dev: 15.0 },
+            Distribution::LogNormal { mu: 0.0, sigma: 0.5 },
+            Distribution::Exponential { rate: 0.5 },
+            Distribution::Triangular { min: 1.0, mode: 5.0, max: 10.0 },
+            Distribution::Beta { alpha: 2.0, beta: 5.0 },
+            Distribution::Beta { alpha: 0.3, beta: 0.4 },
+            Distribution::Poisson { mean: 3.0 },
+            Distribution::Poisson { mean: 250.0 },
+        ];
+        for distribution in distributions {
+            let samples: Vec<f64> = (0..20_000).map(|_| distribution.sample(&mut rng).unwrap()).collect();
+            let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+            let variance = samples.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / samples.len() as f64;
+            // Far more than the standard error of either, but small enough to catch a wrong formula
+            let tolerance = 0.05 * distribution.std_dev();
+            assert!((mean - distribution.mean()).abs() < tolerance, "{distribution}: mean {mean}");
+            let std_dev = variance.sqrt();
+            assert!((std_dev - distribution.std_dev()).abs() < 2.0 * tolerance, "{distribution}: sd {std_dev}");
+        }
+    }
+
+    #[test]
+    fn samples_are_in_bounds() {
+        let mut rng = StdRng::seed_from_u64(12);
+        for _ in 0..10_000 {
+            let x = Distribution::Triangular { min: 1.0, mode: 1.0, max: 2.0 }.sample(&mut rng).unwrap();
+            assert!((1.0..=2.0).contains(&x));
+            let x = Distribution::Beta { alpha: 0.01, beta: 0.01 }.sample(&mut rng).unwrap();
+            assert!((0.0..=1.0).contains(&x));
+            let x = Distribution::Poisson { mean: 12.5 }.sample(&mut rng).unwrap();
+            assert!(x >= 0.0 && x.fract() == 0.0);
+        }
+    }
+
+    #[test]
+    fn check() {
+        assert!(Distribution::Normal { mean: 0.0, std_dev: -1.0 }.check().is_err());
+        assert!(Distribution::Exponential { rate: 0.0 }.check().is_err());
+        assert!(Distribution::Triangular { min: 5.0, mode: 1.0, max: 10.0 }.check().is_err());
+        assert!(Distribution::Poisson { mean: 0.0 }.check().is_ok());
+        assert_eq!(Distribution::new("tri", &[1.0, 2.0]), None);
+    }
+
+    #[test]
+    fn stirling() {
+        let exact: f64 = (2..=30).map(|i| (i as f64).ln()).sum();
+        assert!((ln_factorial(30.0) - exact).abs() < 1e-10);
+    }
+
+    #[test]
+    fn display() {
+        let distribution = Distribution::new("triangular", &[1.0, 5.0, 10.0]).unwrap();
+        assert_eq!(distribution.to_string(), "tri(1, 5, 10)");
+        assert_eq!(format!("{distribution:.1}"), "tri(1.0, 5.0, 10.0)");
+    }
+}
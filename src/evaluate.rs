@@ -1,29 +1,566 @@
 
-use rand::{random, thread_rng, Rng};
+use std::collections::HashSet;
 
-use crate::Expression;
+use rand::{distributions::WeightedIndex, prelude::Distribution, seq::SliceRandom, Rng};
 
-pub fn evaluate_expression(expression: Expression) -> String {
-    match expression {
+use crate::{
+    cards::{
+        deck::Deck,
+        deck_command::DeckCommand,
+    },
+    dice::{
+        atom::DiceExpressionAtom,
+        roll::{atoms_total, roll_dice, AtomResult},
+    },
+    interval::{Interval, RangeText},
+    outcome::{CoinSide, Outcome},
+    parse::{parse_statements, ParseError},
+    value::IntValue,
+    float_text, Expression, Session, MAX_DECIMALS,
+};
+
+/// The most numbers "perm" and "sample" will output,
+/// since they all have to be held in memory
+const MAX_LIST_LEN: u64 = 100_000;
+
+/// The most dice a single atom (e.g. "(n)d6") can roll
+pub(crate) const MAX_DICE: i64 = 1_000;
+
+/// How deeply macros can use other macros, so
+/// that a macro that uses itself can't run forever
+const MAX_MACRO_DEPTH: usize = 16;
+
+/// How many macros a line can call in all, so that macros that
+/// each call several others (e.g. "def b = a; a; a") can't run
+/// for what is practically forever, even without going too deep
+const MAX_MACRO_CALLS: usize = 10_000;
+
+/// How many times a float range that leaves out its lower end (e.g.
+/// "(0, 1)") is sampled again when the lower end is picked, before
+/// deciding there's nothing in between, e.g. "(1, 1.0000000000000002)"
+const MAX_FLOAT_RETRIES: usize = 64;
+
+/// Samples from a distribution are shown with at least this many
+/// decimal places, so that e.g. "beta(2, 5)" isn't rounded to 0 or 1
+const MIN_DISTRIBUTION_DECIMALS: u32 = 2;
+
+/// Every whole number up to this can be held exactly in an f64
+const MAX_EXACT_F64: f64 = 9_007_199_254_740_992.0;
+
+pub fn evaluate_expression(expression: Expression, session: &mut Session) -> Outcome {
+    match try_evaluate_expression(expression, session) {
+        Ok(outcome) => outcome,
+        Err(message) => Outcome::Error { message, column: None },
+    }
+}
+
+fn try_evaluate_expression(expression: Expression, session: &mut Session) -> Result<Outcome, String> {
+    check_output_limit(session)?;
+    let outcome = match expression {
         Expression::CoinFlip => {
-            if random() {
-                "Heads"
+            if session.rng.gen() {
+                Outcome::Coin(CoinSide::Heads)
             } else {
-                "Tails"
-            }.to_string()
+                Outcome::Coin(CoinSide::Tails)
+            }
+        },
+        Expression::IntRange { min, max, interval, step } => {
+            let (min, max) = resolve_range(&min, &max, session)?;
+            let step = step.unwrap_or(1);
+            let (low, high) = interval
+                .int_bounds(min, max, step)
+                .ok_or_else(|| format!("{} has no numbers in it", RangeText { min, max, interval }))?;
+            Outcome::Int(sample_steps(low, high, step, session))
+        },
+        Expression::FloatRange { min, max, interval, decimals, step } => {
+            let value = sample_decimal(min, max, interval, decimals, step, session)?;
+            Outcome::Float { value, decimals: decimals.min(MAX_DECIMALS) }
+        },
+        Expression::Distribution { distribution, decimals } => {
+            let value = distribution.sample(&mut session.rng)?;
+            if distribution.is_discrete() {
+                Outcome::Int(value as i64)
+            } else {
+                Outcome::Float { value, decimals: decimals.clamp(MIN_DISTRIBUTION_DECIMALS, MAX_DECIMALS) }
+            }
+        },
+        Expression::DiceExpression(atoms) => {
+            let (atoms, total) = roll_atoms(&atoms, session)?;
+            Outcome::Dice { atoms, total }
+        },
+        Expression::Pick(items) => {
+            let (names, weights): (Vec<_>, Vec<f64>) = items.into_iter().unzip();
+            // The sum is infinite or NaN if any weight is, and sampling would panic
+            if !weights.iter().sum::<f64>().is_finite() {
+                return Err("pick's weights must add up to a finite number".to_owned());
+            }
+            let distribution = WeightedIndex::new(weights).map_err(|e| e.to_string())?;
+            Outcome::Pick(names[distribution.sample(&mut session.rng)].clone())
+        },
+        Expression::Shuffle(mut items) => {
+            items.shuffle(&mut session.rng);
+            Outcome::Shuffle(items)
+        },
+        Expression::Permutation(min, max) => {
+            let (min, max) = resolve_range(&min, &max, session)?;
+            if max.abs_diff(min) >= MAX_LIST_LEN {
+                return Err(format!("can't permute more than {MAX_LIST_LEN} numbers"));
+            }
+            let mut numbers: Vec<i64> = (min..=max).collect();
+            numbers.shuffle(&mut session.rng);
+            Outcome::Numbers(numbers)
+        },
+        Expression::Sample { count, min, max, sorted } => {
+            let (min, max) = resolve_range(&min, &max, session)?;
+            if count > MAX_LIST_LEN {
+                return Err(format!("can't sample more than {MAX_LIST_LEN} numbers"));
+            }
+            // The range is inclusive, so there is one more
+            // number in it than the difference between the bounds
+            let range_len = max.abs_diff(min) as u128 + 1;
+            if count as u128 > range_len {
+                return Err(format!("can't sample {count} distinct numbers from {min}-{max}"));
+            }
+            let mut numbers: Vec<i64> = sample_offsets(count, range_len, &mut session.rng)
+                .into_iter()
+                // the offset is less than range_len, so this can't overflow an i64
+                .map(|offset| (min as i128 + offset as i128) as i64)
+                .collect();
+            if sorted {
+                numbers.sort_unstable();
+            }
+            Outcome::Numbers(numbers)
         },
-        Expression::IntRange(min, max) => {
-            let mut rng = thread_rng();
-            rng.gen_range(min..=max).to_string()
+        Expression::Deck(command) => {
+            let Session { rng, deck, .. } = session;
+            evaluate_deck_command(command, rng, deck)?
         },
-        Expression::FloatRange(min, max) => {
-            let mut rng = thread_rng();
-            rng.gen_range(min..=max).to_string()
+        Expression::Assign { name, value } => {
+            let (atoms, total) = roll_atoms(&value, session)?;
+            session.variables.insert(name.clone(), total);
+            Outcome::Assign { name, atoms, total }
         },
-        x@Expression::DiceExpression(_) => {
-            dbg!(x);
-            "".to_owned()
+        Expression::Define(definition) => {
+            parse_statements(&definition.body)
+                .map_err(|e| format!("{} in \"{}\"", ParseError::new(&definition.body, e), definition.body))?;
+            let name = definition.name.clone();
+            session.add_macro(definition);
+            Outcome::Message(format!("Defined {name}"))
         },
+        Expression::Undefine(name) => {
+            session
+                .macros
+                .remove(&name)
+                .ok_or_else(|| format!("unknown macro \"{name}\""))?;
+            Outcome::Message(format!("Removed {name}"))
+        },
+        Expression::ListMacros => {
+            if session.macros.is_empty() {
+                Outcome::Message("No macros defined".to_owned())
+            } else {
+                Outcome::Message(session.macros().map(ToString::to_string).collect::<Vec<_>>().join("\n"))
+            }
+        },
+        Expression::CallMacro { name, args } => call_macro(&name, &args, session)?,
+    };
+    session.output_len = session.output_len.saturating_add(output_len(&outcome));
+    check_output_limit(session)?;
+    Ok(outcome)
+}
+
+fn check_output_limit(session: &Session) -> Result<(), String> {
+    match session.output_limit {
+        Some(limit) if session.output_limit_reached() => Err(format!("can't output more than {limit} values")),
+        _ => Ok(()),
+    }
+}
+
+/// How many values (dice, numbers, cards, etc.) an outcome counts as
+/// towards the session's output limit. A macro's outcomes are counted
+/// as they are evaluated, so the macro itself only counts as one.
+fn output_len(outcome: &Outcome) -> usize {
+    let atoms_len = |atoms: &[AtomResult]| -> usize {
+        atoms
+            .iter()
+            .map(|atom| match atom {
+                AtomResult::Roll { dice, .. } => dice.len().max(1),
+                _ => 1,
+            })
+            .sum()
+    };
+    match outcome {
+        Outcome::Dice { atoms, .. } | Outcome::Assign { atoms, .. } => atoms_len(atoms),
+        Outcome::Shuffle(items) => items.len(),
+        Outcome::Numbers(numbers) => numbers.len(),
+        Outcome::Cards { cards, .. } => cards.len(),
+        _ => 1,
+    }
+}
+
+fn call_macro(name: &str, args: &[IntValue], session: &mut Session) -> Result<Outcome, String> {
+    let Some(definition) = session.macros.get(name).cloned() else {
+        // A lone variable name is parsed as a macro call,
+        // so show the variable's value if there is one
+        if args.is_empty() && session.variable(name).is_some() {
+            let atom = DiceExpressionAtom::Variable { name: name.to_owned(), subtracted: false };
+            let (atoms, total) = roll_atoms(&[atom], session)?;
+            return Ok(Outcome::Dice { atoms, total });
+        }
+        return Err(format!("unknown macro or variable \"{name}\""));
+    };
+    if args.len() != definition.params.len() {
+        return Err(format!(
+            "{name} takes {} arguments but was given {}",
+            definition.params.len(),
+            args.len()
+        ));
+    }
+    if session.macro_depth >= MAX_MACRO_DEPTH {
+        return Err(format!("macros can only use other macros {MAX_MACRO_DEPTH} levels deep"));
+    }
+    if session.macro_calls >= MAX_MACRO_CALLS {
+        return Err(format!("a line can only call macros {MAX_MACRO_CALLS} times"));
+    }
+    session.macro_calls += 1;
+
+    // The parameters are set as variables while the body is evaluated,
+    // and any variables they hide are put back afterwards
+    let mut hidden_variables = vec![];
+    for (param, arg) in definition.params.iter().zip(args) {
+        let value = resolve(arg, session)?;
+        hidden_variables.push((param, session.variables.insert(param.clone(), value)));
+    }
+    session.macro_depth += 1;
+    let outcomes: Result<Vec<Outcome>, String> = match parse_statements(&definition.body) {
+        Ok((_remainder, expressions)) => expressions
+            .into_iter()
+            .map(|expression| try_evaluate_expression(expression, session))
+            .collect(),
+        Err(e) => Err(ParseError::new(&definition.body, e).to_string()),
+    };
+    session.macro_depth -= 1;
+    for (param, hidden) in hidden_variables.into_iter().rev() {
+        match hidden {
+            Some(value) => session.variables.insert(param.clone(), value),
+            None => session.variables.remove(param),
+        };
+    }
+
+    Ok(Outcome::Macro { name: name.to_owned(), outcomes: outcomes? })
+}
+
+/// Work out the value of an integer that may depend on
+/// variables or dice, e.g. the "maxhp" in "1-maxhp"
+fn resolve(value: &IntValue, session: &mut Session) -> Result<i64, String> {
+    match value {
+        IntValue::Literal(value) => Ok(*value),
+        IntValue::Variable(name) => session.variable(name).ok_or_else(|| format!("unknown variable \"{name}\"")),
+        IntValue::Expression(atoms) => Ok(roll_atoms(atoms, session)?.1),
+    }
+}
+
+/// Resolve the bounds of an inclusive range, e.g. "1-maxhp"
+fn resolve_range(min: &IntValue, max: &IntValue, session: &mut Session) -> Result<(i64, i64), String> {
+    let min = resolve(min, session)?;
+    let max = resolve(max, session)?;
+    if min > max {
+        return Err(format!("{min} is greater than {max}"));
+    }
+    Ok((min, max))
+}
+
+/// A random whole number from `low` to `high` that's a multiple of `step` away from `low`
+fn sample_steps(low: i64, high: i64, step: u64, session: &mut Session) -> i64 {
+    if step == 1 {
+        return session.rng.gen_range(low..=high);
+    }
+    // There are at most u64::MAX steps between two i64s
+    let steps = ((high as i128 - low as i128) / step as i128) as u64;
+    (low as i128 + session.rng.gen_range(0..=steps) as i128 * step as i128) as i64
+}
+
+/// A random float in the range with the given number of decimal places, e.g. 1.37
+/// for "1.00-2.00". It's picked from the numbers with that many places (or `step`
+/// apart), rather than rounded, so that e.g. "[0.00, 1)" can't give 1.00.
+fn sample_decimal(
+    min: f64,
+    max: f64,
+    interval: Interval,
+    decimals: u32,
+    step: Option<f64>,
+    session: &mut Session,
+) -> Result<f64, String> {
+    let range = RangeText {
+        min: float_text(min, decimals),
+        max: float_text(max, decimals),
+        interval,
+    };
+    if min > max {
+        return Err(format!("{} is greater than {}", range.min, range.max));
+    }
+    // Each number in the range is a whole number of 1 / scale
+    let scale = 10f64.powi(decimals.min(i32::MAX as u32) as i32);
+    let scaled = |value: f64| Some((value * scale).round()).filter(|value| value.abs() <= MAX_EXACT_F64);
+    match (scaled(min), scaled(max), step.map_or(Some(1.0), scaled)) {
+        (Some(low), Some(high), Some(scaled_step)) if scaled_step >= 1.0 => {
+            let (low, high) = interval
+                .int_bounds(low as i64, high as i64, scaled_step as u64)
+                .ok_or_else(|| format!("{range} has no numbers in it"))?;
+            Ok(sample_steps(low, high, scaled_step as u64, session) as f64 / scale)
+        }
+        // There are too many numbers with that many places to pick from,
+        // so any float in the range is picked, and only shown rounded
+        _ if step.is_none() => sample_float(min, max, interval, &range, session),
+        _ => Err(format!("{range} has too many decimal places to step through")),
+    }
+}
+
+/// A random float in the range, leaving out whichever ends the interval does
+fn sample_float(
+    min: f64,
+    max: f64,
+    interval: Interval,
+    range: &RangeText<String>,
+    session: &mut Session,
+) -> Result<f64, String> {
+    if !(max - min).is_finite() {
+        return Err(format!("{range} is too large"));
+    }
+    if min == max && interval != Interval::Closed {
+        return Err(format!("{range} has no numbers in it"));
+    }
+    let rng = &mut session.rng;
+    match interval {
+        Interval::Closed => Ok(rng.gen_range(min..=max)),
+        Interval::ClosedOpen => Ok(rng.gen_range(min..max)),
+        Interval::OpenClosed => (0..MAX_FLOAT_RETRIES)
+            .map(|_| rng.gen_range(min..=max))
+            .find(|value| *value != min)
+            .ok_or_else(|| format!("{range} has no numbers in it")),
+        Interval::Open => (0..MAX_FLOAT_RETRIES)
+            .map(|_| rng.gen_range(min..max))
+            .find(|value| *value != min)
+            .ok_or_else(|| format!("{range} has no numbers in it")),
+    }
+}
+
+/// Roll each atom, and add up the total
+fn roll_atoms(atoms: &[DiceExpressionAtom], session: &mut Session) -> Result<(Vec<AtomResult>, i64), String> {
+    let atoms = atoms.iter().map(|atom| roll_atom(atom, session)).collect::<Result<Vec<_>, _>>()?;
+    let total = atoms_total(&atoms).ok_or_else(|| "result is out of range".to_owned())?;
+    Ok((atoms, total))
+}
+
+fn roll_atom(atom: &DiceExpressionAtom, session: &mut Session) -> Result<AtomResult, String> {
+    match atom {
+        DiceExpressionAtom::Constant(value) => Ok(AtomResult::Constant(*value)),
+        DiceExpressionAtom::Variable { name, subtracted } => Ok(AtomResult::Variable {
+            name: name.clone(),
+            value: resolve(&IntValue::Variable(name.clone()), session)?,
+            subtracted: *subtracted,
+        }),
+        DiceExpressionAtom::Roll {
+            number_of_dice,
+            number_of_sides,
+            advantage_status,
+            keep_drop,
+            reroll,
+            subtracted,
+        } => {
+            let number_of_dice = resolve(number_of_dice, session)?;
+            if !(0..=MAX_DICE).contains(&number_of_dice) {
+                return Err(format!("can't roll {number_of_dice} dice (the most is {MAX_DICE})"));
+            }
+            if *number_of_sides == 0 {
+                return Err("dice need at least one side".to_owned());
+            }
+            let number_of_dice = number_of_dice as usize;
+            let dice = roll_dice(
+                number_of_dice,
+                *number_of_sides,
+                advantage_status,
+                keep_drop,
+                *reroll,
+                &mut session.rng,
+            );
+            Ok(AtomResult::Roll {
+                number_of_dice,
+                number_of_sides: *number_of_sides,
+                dice,
+                subtracted: *subtracted,
+            })
+        }
+    }
+}
+
+fn evaluate_deck_command<R: Rng + ?Sized>(command: DeckCommand, rng: &mut R, deck: &mut Deck) -> Result<Outcome, String> {
+    let outcome = match command {
+        DeckCommand::New(kind) => {
+            *deck = Deck::new(&kind);
+            Outcome::Message(format!("New deck of {} cards", deck.len()))
+        }
+        DeckCommand::Draw(amount) => {
+            if deck.remaining() == 0 {
+                return Err("the deck is out of cards (use \"deck shuffle\" to put them back)".to_owned());
+            }
+            let cards: Vec<String> = deck.draw(amount, rng).iter().map(ToString::to_string).collect();
+            Outcome::Cards { ran_out: cards.len() < amount, cards }
+        }
+        DeckCommand::Shuffle => {
+            deck.shuffle();
+            Outcome::Message(format!("Shuffled {} cards", deck.remaining()))
+        }
+        DeckCommand::Remaining => Outcome::Message(format!("{} of {} cards remaining", deck.remaining(), deck.len())),
+        DeckCommand::Discard => Outcome::Message(format!("Discarded {} cards", deck.discard())),
+    };
+    Ok(outcome)
+}
+
+/// Pick `count` distinct numbers from `0..range_len` in a random order.
+/// This uses Robert Floyd's sampling algorithm, which only
+/// needs memory for the numbers picked (not the whole range),
+/// so it works for huge ranges like 1-1_000_000_000_000
+fn sample_offsets<R: Rng + ?Sized>(count: u64, range_len: u128, rng: &mut R) -> Vec<u128> {
+    let mut picked = HashSet::with_capacity(count as usize);
+    let mut offsets = Vec::with_capacity(count as usize);
+    for j in (range_len - count as u128)..range_len {
+        let t = rng.gen_range(0..=j);
+        let offset = if picked.contains(&t) { j } else { t };
+        picked.insert(offset);
+        offsets.push(offset);
+    }
+    // Floyd's algorithm picks each set of numbers with equal
+    // probability, but not each ordering, so shuffle to fix that
+    offsets.shuffle(rng);
+    offsets
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use rand::thread_rng;
+
+    use crate::Session;
+
+    use super::sample_offsets;
+
+    #[test]
+    fn seeded_shuffle_is_reproducible() {
+        for expression in ["shuffle a, b, c, d, e, f", "perm 1-20"] {
+            assert_eq!(
+                Session::seeded(7).eval(expression),
+                Session::seeded(7).eval(expression)
+            );
+        }
+    }
+
+    #[test]
+    fn sample_is_distinct_and_in_range() {
+        let offsets = sample_offsets(49, 49, &mut thread_rng());
+        let distinct: HashSet<_> = offsets.iter().collect();
+        assert_eq!(distinct.len(), 49);
+        assert!(offsets.iter().all(|&offset| offset < 49));
+
+        let offsets = sample_offsets(5, 1_000_000_000_000, &mut thread_rng());
+        let distinct: HashSet<_> = offsets.iter().collect();
+        assert_eq!(distinct.len(), 5);
+    }
+
+    #[test]
+    fn ranges() {
+        let mut session = Session::seeded(3);
+        for _ in 0..200 {
+            let value: i64 = session.eval("(0, 100) step 5").parse().unwrap();
+            assert!(value > 0 && value < 100 && value % 5 == 0);
+            let value = session.eval("[0.00, 1)");
+            assert!(value.starts_with("0.") && value.len() == 4, "{value}");
+            let value = session.eval("-1..1 step 0.5");
+            assert!(["-1.0", "-0.5", "0.0", "0.5"].contains(&value.as_str()), "{value}");
+        }
+        assert_eq!(session.eval("(1.0, 1.1)"), "Error: (1.0, 1.1) has no numbers in it");
+        assert_eq!(session.eval("1.5-1.50"), "1.50");
+        assert_eq!(session.eval("0-1e-70000").len(), 342);
+    }
+
+    #[test]
+    fn distributions() {
+        let mut session = Session::seeded(5);
+        let value = session.eval("normal(100, 15)");
+        assert!(value.split_once('.').is_some_and(|(_, decimals)| decimals.len() == 2), "{value}");
+        let value = session.eval("beta(2, 5.000)");
+        assert!(value.starts_with("0.") && value.len() == 5, "{value}");
+        assert!(session.eval("poisson(3)").parse::<i64>().is_ok());
+        assert_eq!(session.eval("tri(5, 1, 10)"), "Error: tri needs min <= mode <= max, with min less than max");
+        assert_eq!(session.eval("lognormal(1000, 1)"), "Error: lognormal's sample is too large");
+    }
+
+    #[test]
+    fn variables() {
+        let mut session = Session::new();
+        assert_eq!(session.eval("str = 3"), "str = 3");
+        assert_eq!(session.eval("prof = +2"), "prof = 2");
+        assert_eq!(session.eval("str + prof - 1"), "str (3) + prof (2) - 1 = 4");
+        assert_eq!(session.eval("(str)d1 + 1"), "3d1 [1, 1, 1] + 1 = 4");
+        assert_eq!(session.eval("maxhp = 1"), "maxhp = 1");
+        assert_eq!(session.eval("1-maxhp"), "1");
+        assert_eq!(session.eval("d20 + dex"), "Error: unknown variable \"dex\"");
+        assert_eq!(session.eval("str"), "str (3) = 3");
+    }
+
+    #[test]
+    fn out_of_range() {
+        let mut session = Session::new();
+        assert_eq!(session.eval("9223372036854775807 + 1 + 0"), "Error: result is out of range");
+        assert_eq!(session.eval("x = 9223372036854775807 + 9223372036854775807"), "Error: result is out of range");
+        assert_eq!(session.eval("x = -9223372036854775807 - 1"), "x = -9223372036854775808");
+        assert_eq!(session.eval("1d6 - x"), "Error: result is out of range");
+        assert_eq!(session.eval("d1 - 2 - x"), "1d1 [1] - 2 - x (-9223372036854775808) = 9223372036854775807");
+        assert_eq!(session.eval("d1 - 1 - x"), "Error: result is out of range");
+    }
+
+    #[test]
+    fn pick_weights() {
+        let mut session = Session::new();
+        assert_eq!(session.eval("pick a:1e39, b:0"), "a");
+        assert_eq!(session.eval("pick a:1e308, b:1e308"), "Error: pick's weights must add up to a finite number");
+        assert!(session.eval("pick a:1e400, b").starts_with("Error: "));
+    }
+
+    #[test]
+    fn several_expressions() {
+        let mut session = Session::new();
+        assert_eq!(session.eval("1d1+5; 2d1 , x = 3, x"), "1d1 [1] + 5 = 6\n2d1 [1, 1] = 2\nx = 3\nx (3) = 3");
+        assert_eq!(session.eval("y; 1-1"), "Error: unknown macro or variable \"y\"\n1");
+    }
+
+    #[test]
+    fn macros() {
+        let mut session = Session::new();
+        assert_eq!(session.eval("def two = 2d1 ; 1d1 + 1"), "Defined two");
+        assert_eq!(session.eval("two"), "2d1 [1, 1] = 2\n1d1 [1] + 1 = 2");
+        assert_eq!(session.eval("def smite(n) = (n+1)d1"), "Defined smite");
+        assert_eq!(session.eval("smite(2)"), "3d1 [1, 1, 1] = 3");
+        assert_eq!(session.variable("n"), None);
+        assert_eq!(session.eval("smite"), "Error: smite takes 1 arguments but was given 0");
+        assert_eq!(session.eval("def forever = forever"), "Defined forever");
+        assert!(session.eval("forever").starts_with("Error: macros can only use"));
+        assert_eq!(session.eval("undef forever"), "Removed forever");
+        assert_eq!(session.eval("macros"), "def smite(n) = (n+1)d1\ndef two = 2d1 ; 1d1 + 1");
+    }
 
+    #[test]
+    fn macros_that_fan_out() {
+        let mut session = Session::new();
+        session.eval("def m0 = 1d1");
+        // Each macro calls the one before it ten times,
+        // so "m9" would roll ten billion dice
+        for level in 1..10 {
+            let calls = vec![format!("m{}", level - 1); 10].join("; ");
+            session.eval(&format!("def m{level} = {calls}"));
+        }
+        assert_eq!(session.eval("m9"), "Error: a line can only call macros 10000 times");
+        // The count starts again on the next line
+        assert!(session.evaluate("m3").is_ok());
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,390 @@
+//! The syntax reference shown by the REPL's "help" command.
+//! The same table decides which words the REPL completes.
+
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Topic {
+    Dice,
+    Modifiers,
+    Ranges,
+    Distributions,
+    Lists,
+    Cards,
+    Variables,
+    Macros,
+    Repl,
+}
+
+impl Topic {
+    pub const ALL: [Topic; 9] = [
+        Topic::Dice,
+        Topic::Modifiers,
+        Topic::Ranges,
+        Topic::Distributions,
+        Topic::Lists,
+        Topic::Cards,
+        Topic::Variables,
+        Topic::Macros,
+        Topic::Repl,
+    ];
+
+    /// The name used to ask for the topic, e.g. "help dice"
+    pub fn name(self) -> &'static str {
+        match self {
+            Topic::Dice => "dice",
+            Topic::Modifiers => "modifiers",
+            Topic::Ranges => "ranges",
+            Topic::Distributions => "distributions",
+            Topic::Lists => "lists",
+            Topic::Cards => "cards",
+            Topic::Variables => "variables",
+            Topic::Macros => "macros",
+            Topic::Repl => "repl",
+        }
+    }
+}
+
+pub struct HelpEntry {
+    pub topic: Topic,
+    /// Words that can be looked up with `help <word>`, e.g. "adv"
+    pub names: &'static [&'static str],
+    /// Words the REPL completes, e.g. "advantage"
+    pub completions: &'static [&'static str],
+    pub syntax: &'static str,
+    pub summary: &'static str,
+    pub examples: &'static [&'static str],
+}
+
+pub const HELP: &[HelpEntry] = &[
+    HelpEntry {
+        topic: Topic::Dice,
+        names: &["d", "roll"],
+        completions: &[],
+        syntax: "NdS",
+        summary: "Roll N dice with S sides and add them up. N can be left out for 1 die, or be a variable or (expression).",
+        examples: &["3d6", "d20 + 5", "2d8 - 1d4 + str", "(lvl)d6"],
+    },
+    HelpEntry {
+        topic: Topic::Dice,
+        names: &["shorthand", "+"],
+        completions: &[],
+        syntax: "+N, -N",
+        summary: "Roll a d20 and add N. Advantage, disadvantage and rerolls can follow N.",
+        examples: &["+5", "-1", "3a", "2d"],
+    },
+    HelpEntry {
+        topic: Topic::Dice,
+        names: &[";", ","],
+        completions: &[],
+        syntax: "A; B",
+        summary: "Evaluate several expressions, one after the other. \",\" works too.",
+        examples: &["d20 + 5; 2d6 + 3", "d20, d20"],
+    },
+    HelpEntry {
+        topic: Topic::Modifiers,
+        names: &["a", "adv", "advantage"],
+        completions: &["adv", "advantage"],
+        syntax: "a, adv, advantage",
+        summary: "Roll the dice twice and keep the higher total.",
+        examples: &["d20adv", "2a"],
+    },
+    HelpEntry {
+        topic: Topic::Modifiers,
+        names: &["d", "dis", "disadv", "disadvantage"],
+        completions: &["dis", "disadv", "disadvantage"],
+        syntax: "d, dis, disadv, disadvantage",
+        summary: "Roll the dice twice and keep the lower total. \
+            In shorthand a \"d\" after the number is disadvantage, so \"2d\" is a d20 + 2 with disadvantage.",
+        examples: &["d20dis", "2d"],
+    },
+    HelpEntry {
+        topic: Topic::Modifiers,
+        names: &["r", "reroll"],
+        completions: &[],
+        syntax: "rN",
+        summary: "Reroll each die that comes up N or lower, once.",
+        examples: &["2d6r1", "4d6r2"],
+    },
+    HelpEntry {
+        topic: Topic::Modifiers,
+        names: &["k", "kh", "kl", "keep"],
+        completions: &[],
+        syntax: "kN, khN, klN",
+        summary: "Keep the highest N dice, or the lowest with \"kl\".",
+        examples: &["4d6k3", "2d20kl1"],
+    },
+    HelpEntry {
+        topic: Topic::Modifiers,
+        names: &["d", "dh", "dl", "drop"],
+        completions: &[],
+        syntax: "dN, dlN, dhN",
+        summary: "Drop the lowest N dice, or the highest with \"dh\".",
+        examples: &["4d6dl1", "3d20dh1"],
+    },
+    HelpEntry {
+        topic: Topic::Ranges,
+        names: &["-", "range"],
+        completions: &[],
+        syntax: "MIN-MAX",
+        summary: "A random whole number from MIN to MAX. Either end can be a variable.",
+        examples: &["1-100", "-5-5", "1-maxhp"],
+    },
+    HelpEntry {
+        topic: Topic::Ranges,
+        names: &["float", "decimal"],
+        completions: &[],
+        syntax: "MIN.0-MAX",
+        summary: "A random decimal number from MIN to MAX, if either end has a decimal point. \
+                  It has as many decimal places as the range is written with.",
+        examples: &["0.0-1", "1.5-2.5", "1.00-2.00"],
+    },
+    HelpEntry {
+        topic: Topic::Ranges,
+        names: &["..", "..=", "interval", "exclusive"],
+        completions: &[],
+        syntax: "MIN..MAX, MIN..=MAX, [MIN, MAX)",
+        summary: "A range that leaves out MAX (\"..\") or includes it (\"..=\"). \
+                  In brackets, \"[\" and \"]\" include an end and \"(\" and \")\" leave it out.",
+        examples: &["0..10", "1..=6", "[0.0, 1)", "(0, 1]"],
+    },
+    HelpEntry {
+        topic: Topic::Ranges,
+        names: &["step"],
+        completions: &["step"],
+        syntax: "RANGE step N",
+        summary: "Only pick numbers that are a multiple of N from the lower end of the range.",
+        examples: &["0-100 step 5", "0.0..1 step 0.25"],
+    },
+    HelpEntry {
+        topic: Topic::Ranges,
+        names: &["coin"],
+        completions: &["coin"],
+        syntax: "coin",
+        summary: "Flip a coin.",
+        examples: &["coin"],
+    },
+    HelpEntry {
+        topic: Topic::Distributions,
+        names: &["normal", "gaussian"],
+        completions: &["normal"],
+        syntax: "normal(MEAN, SD)",
+        summary: "A number from a bell curve with the given mean and standard deviation. \
+                  Decimal numbers have at least 2 decimal places, or as many as the parameters have.",
+        examples: &["normal(100, 15)", "normal(0, 1.000)"],
+    },
+    HelpEntry {
+        topic: Topic::Distributions,
+        names: &["lognormal"],
+        completions: &["lognormal"],
+        syntax: "lognormal(MU, SIGMA)",
+        summary: "A positive number whose logarithm is from normal(MU, SIGMA), e.g. for prices or durations.",
+        examples: &["lognormal(0, 0.5)"],
+    },
+    HelpEntry {
+        topic: Topic::Distributions,
+        names: &["exp", "exponential"],
+        completions: &["exp", "exponential"],
+        syntax: "exp(RATE)",
+        summary: "The time until the next event, for events that happen RATE times per unit of time on average.",
+        examples: &["exp(0.5)", "exponential(2)"],
+    },
+    HelpEntry {
+        topic: Topic::Distributions,
+        names: &["tri", "triangular"],
+        completions: &["tri", "triangular"],
+        syntax: "tri(MIN, MODE, MAX)",
+        summary: "A number from MIN to MAX, most likely to be near MODE, e.g. for a rough estimate.",
+        examples: &["tri(1, 5, 10)"],
+    },
+    HelpEntry {
+        topic: Topic::Distributions,
+        names: &["beta"],
+        completions: &["beta"],
+        syntax: "beta(ALPHA, BETA)",
+        summary: "A number from 0 to 1, e.g. for a rate after ALPHA successes and BETA failures.",
+        examples: &["beta(2, 5)"],
+    },
+    HelpEntry {
+        topic: Topic::Distributions,
+        names: &["poisson"],
+        completions: &["poisson"],
+        syntax: "poisson(MEAN)",
+        summary: "How many events happen, when MEAN happen on average.",
+        examples: &["poisson(3)"],
+    },
+    HelpEntry {
+        topic: Topic::Lists,
+        names: &["pick"],
+        completions: &["pick"],
+        syntax: "pick A, B[:WEIGHT], ...",
+        summary: "Pick one item. Items without a weight have a weight of 1.",
+        examples: &["pick red, green, blue", "pick common:10, rare:1"],
+    },
+    HelpEntry {
+        topic: Topic::Lists,
+        names: &["shuffle"],
+        completions: &["shuffle"],
+        syntax: "shuffle A, B, ...",
+        summary: "Put the items in a random order.",
+        examples: &["shuffle alice, bob, carol"],
+    },
+    HelpEntry {
+        topic: Topic::Lists,
+        names: &["perm"],
+        completions: &["perm"],
+        syntax: "perm MIN-MAX",
+        summary: "Put the numbers from MIN to MAX in a random order.",
+        examples: &["perm 1-10"],
+    },
+    HelpEntry {
+        topic: Topic::Lists,
+        names: &["sample", "from", "sorted"],
+        completions: &["sample", "from", "sorted"],
+        syntax: "sample N from MIN-MAX [sorted]",
+        summary: "N different numbers from MIN to MAX.",
+        examples: &["sample 6 from 1-49", "sample 6 from 1-49 sorted"],
+    },
+    HelpEntry {
+        topic: Topic::Cards,
+        names: &["deck", "new", "jokers", "tarot", "custom"],
+        completions: &["deck", "new", "jokers", "tarot", "custom"],
+        syntax: "deck new [jokers | tarot | custom A, B, ...]",
+        summary: "Start a new deck: 52 cards, with jokers, a tarot deck, or your own cards.",
+        examples: &["deck new", "deck new tarot", "deck new custom fire, water, earth"],
+    },
+    HelpEntry {
+        topic: Topic::Cards,
+        names: &["deck", "remaining", "discard"],
+        completions: &["shuffle", "remaining", "discard"],
+        syntax: "deck shuffle | remaining | discard",
+        summary: "Put every card back, count the cards left, or discard the cards drawn so far.",
+        examples: &["deck shuffle", "deck remaining", "deck discard"],
+    },
+    HelpEntry {
+        topic: Topic::Cards,
+        names: &["draw"],
+        completions: &["draw"],
+        syntax: "draw [N]",
+        summary: "Draw N cards (1 if left out) from the deck. Cards stay drawn until the deck is shuffled.",
+        examples: &["draw", "draw 5"],
+    },
+    HelpEntry {
+        topic: Topic::Variables,
+        names: &["=", "variable"],
+        completions: &[],
+        syntax: "NAME = DICE",
+        summary: "Roll and save the total in a variable, which can then be used in place of a number.",
+        examples: &["str = 3", "hp = 4d8 + 4", "d20 + str"],
+    },
+    HelpEntry {
+        topic: Topic::Macros,
+        names: &["def"],
+        completions: &["def"],
+        syntax: "def NAME[(A, B, ...)] = EXPRESSION",
+        summary: "Define a macro, which evaluates EXPRESSION when NAME is used. Macros are saved between runs.",
+        examples: &["def attack = d20 + 5; 1d8 + 3", "def fireball(n) = (n)d6"],
+    },
+    HelpEntry {
+        topic: Topic::Macros,
+        names: &["undef", "macros"],
+        completions: &["undef", "macros"],
+        syntax: "undef NAME, macros",
+        summary: "Remove a macro, or list the macros that are defined.",
+        examples: &["undef attack", "macros"],
+    },
+    HelpEntry {
+        topic: Topic::Repl,
+        names: &["edit"],
+        completions: &["edit"],
+        syntax: "edit NAME",
+        summary: "Put a macro's definition in the next line, so it can be changed.",
+        examples: &["edit attack"],
+    },
+    HelpEntry {
+        topic: Topic::Repl,
+        names: &["help"],
+        completions: &["help"],
+        syntax: "help [TOPIC | WORD]",
+        summary: "Show this help, the help for a topic, or the help for a word, e.g. \"help adv\".",
+        examples: &["help", "help dice", "help adv"],
+    },
+];
+
+fn write_entry(text: &mut String, entry: &HelpEntry) {
+    writeln!(text, "  {}", entry.syntax).unwrap();
+    writeln!(text, "      {}", entry.summary).unwrap();
+    writeln!(text, "      e.g. {}", entry.examples.join("  |  ")).unwrap();
+}
+
+/// The help for `query`, which is a topic (e.g. "dice"), a word (e.g. "adv"),
+/// or empty for an overview of every topic
+pub fn help(query: &str) -> Result<String, String> {
+    let query = query.trim();
+    let mut text = String::new();
+    if query.is_empty() {
+        for topic in Topic::ALL {
+            writeln!(text, "{}:", topic.name()).unwrap();
+            for entry in HELP.iter().filter(|entry| entry.topic == topic) {
+                writeln!(text, "  {:<36} {}", entry.syntax, entry.summary).unwrap();
+            }
+        }
+        text.push_str("Type \"help <topic>\" or \"help <word>\" (e.g. \"help adv\") for examples");
+        return Ok(text);
+    }
+
+    if let Some(topic) = Topic::ALL.into_iter().find(|topic| topic.name() == query) {
+        writeln!(text, "{}:", topic.name()).unwrap();
+        HELP.iter()
+            .filter(|entry| entry.topic == topic)
+            .for_each(|entry| write_entry(&mut text, entry));
+    } else {
+        HELP.iter()
+            .filter(|entry| entry.names.contains(&query))
+            .for_each(|entry| write_entry(&mut text, entry));
+    }
+    if text.is_empty() {
+        return Err(format!("no help for \"{query}\" (type \"help\" for a list of topics)"));
+    }
+    Ok(text.trim_end().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        help::{help, Topic, HELP},
+        keywords::{COMMANDS, DECK_COMMANDS, DECK_PRESETS, DISTRIBUTIONS, ROLL_MODIFIERS},
+        parse::parse_statements,
+    };
+
+    #[test]
+    fn every_keyword_is_completed() {
+        let keywords = COMMANDS.iter().chain(&DECK_COMMANDS).chain(&DECK_PRESETS).chain(&DISTRIBUTIONS);
+        for word in keywords.chain(&ROLL_MODIFIERS) {
+            assert!(
+                HELP.iter().any(|entry| entry.completions.contains(word)),
+                "\"{word}\" isn't in the help table"
+            );
+        }
+    }
+
+    #[test]
+    fn examples_parse() {
+        for entry in HELP.iter().filter(|entry| entry.topic != Topic::Repl) {
+            for example in entry.examples {
+                assert!(parse_statements(example).is_ok(), "\"{example}\" doesn't parse");
+            }
+        }
+    }
+
+    #[test]
+    fn lookup() {
+        assert!(help("").unwrap().contains("sample N from MIN-MAX [sorted]"));
+        assert!(help("dice").unwrap().contains("3d6"));
+        assert!(help("dis").unwrap().contains("\"2d\" is a d20 + 2 with disadvantage"));
+        // "d" is both a die and a modifier
+        let d = help("d").unwrap();
+        assert!(d.contains("NdS") && d.contains("dN, dlN, dhN"));
+        assert!(help("nope").is_err());
+    }
+}
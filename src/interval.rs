@@ -0,0 +1,98 @@
+use std::fmt::{self, Display};
+
+/// Which ends of a range can be picked, e.g. both in "1-6",
+/// or only the lower one in "0..1" and "[0, 1)"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Interval {
+    /// Both ends, e.g. "1-6", "1..=6" or "[1, 6]"
+    #[default]
+    Closed,
+    /// Only the lower end, e.g. "0..1" or "[0, 1)"
+    ClosedOpen,
+    /// Only the upper end, e.g. "(0, 1]"
+    OpenClosed,
+    /// Neither end, e.g. "(0, 1)"
+    Open,
+}
+
+impl Interval {
+    pub fn new(includes_min: bool, includes_max: bool) -> Self {
+        match (includes_min, includes_max) {
+            (true, true) => Interval::Closed,
+            (true, false) => Interval::ClosedOpen,
+            (false, true) => Interval::OpenClosed,
+            (false, false) => Interval::Open,
+        }
+    }
+
+    pub fn includes_min(self) -> bool {
+        matches!(self, Interval::Closed | Interval::ClosedOpen)
+    }
+
+    pub fn includes_max(self) -> bool {
+        matches!(self, Interval::Closed | Interval::OpenClosed)
+    }
+
+    /// The lowest and highest whole numbers in the range that are a multiple
+    /// of `step` away from `min`, e.g. 2 and 5 for "(1, 6)" with a step of 1,
+    /// or 5 and 95 for "(0, 100)" with a step of 5. `None` if there aren't
+    /// any, e.g. for "1..1".
+    pub fn int_bounds(self, min: i64, max: i64, step: u64) -> Option<(i64, i64)> {
+        let span = max as i128 - min as i128;
+        let step = step as i128;
+        let first = if self.includes_min() { 0 } else { 1 };
+        let mut last = span.div_euclid(step);
+        if !self.includes_max() && last * step == span {
+            last -= 1;
+        }
+        // Both ends are between min and max, so they fit in an i64
+        (first <= last).then(|| ((min as i128 + first * step) as i64, (min as i128 + last * step) as i64))
+    }
+}
+
+/// A range written the way it's typed, e.g. "1-6", "0..1" or "(0, 1]"
+pub(crate) struct RangeText<T> {
+    pub min: T,
+    pub max: T,
+    pub interval: Interval,
+}
+
+impl<T: Display> Display for RangeText<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let RangeText { min, max, interval } = self;
+        match interval {
+            Interval::Closed => write!(f, "{min}-{max}"),
+            Interval::ClosedOpen => {
+                // "5...6" would be read as "5..", then ".6"
+                let min = min.to_string();
+                if min.ends_with('.') {
+                    write!(f, "[{min}, {max})")
+                } else {
+                    write!(f, "{min}..{max}")
+                }
+            }
+            // There's no shorthand for leaving out the lower end
+            Interval::OpenClosed => write!(f, "({min}, {max}]"),
+            Interval::Open => write!(f, "({min}, {max})"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interval::Interval;
+
+    #[test]
+    fn int_bounds() {
+        assert_eq!(Interval::Closed.int_bounds(1, 6, 1), Some((1, 6)));
+        assert_eq!(Interval::ClosedOpen.int_bounds(0, 10, 1), Some((0, 9)));
+        assert_eq!(Interval::Open.int_bounds(1, 6, 1), Some((2, 5)));
+        assert_eq!(Interval::ClosedOpen.int_bounds(1, 1, 1), None);
+        assert_eq!(Interval::OpenClosed.int_bounds(i64::MAX, i64::MAX, 1), None);
+        assert_eq!(Interval::Closed.int_bounds(i64::MIN, i64::MAX, 1), Some((i64::MIN, i64::MAX)));
+        assert_eq!(Interval::Open.int_bounds(0, 100, 5), Some((5, 95)));
+        assert_eq!(Interval::ClosedOpen.int_bounds(0, 99, 5), Some((0, 95)));
+        assert_eq!(Interval::OpenClosed.int_bounds(0, 4, 5), None);
+    }
+}
@@ -0,0 +1,24 @@
+//! The words that have a meaning of their own in expressions,
+//! e.g. so that the REPL can complete them
+
+/// Words that start a command, or are part of one, e.g. "sample 6 from 1-49"
+pub const COMMANDS: [&str; 13] = [
+    "coin", "pick", "shuffle", "perm", "sample", "from", "sorted", "step", "deck", "draw", "def", "undef",
+    "macros",
+];
+
+/// The distributions that can be sampled, e.g. "normal(100, 15)". They're only
+/// keywords when followed by their parameters, so e.g. "exp" can be a variable.
+pub const DISTRIBUTIONS: [&str; 8] = [
+    "normal", "lognormal", "exp", "exponential", "tri", "triangular", "beta", "poisson",
+];
+
+/// The words that come after "deck", e.g. "deck shuffle"
+pub const DECK_COMMANDS: [&str; 4] = ["new", "shuffle", "remaining", "discard"];
+
+/// The kinds of deck "deck new" can make, e.g. "deck new tarot"
+pub const DECK_PRESETS: [&str; 3] = ["jokers", "tarot", "custom"];
+
+/// Modifiers that are written out as words after a
+/// dice roll, e.g. the "adv" in "d20adv"
+pub const ROLL_MODIFIERS: [&str; 5] = ["adv", "advantage", "dis", "disadv", "disadvantage"];
@@ -1,24 +1,484 @@
-use dice::atom::DiceExpressionAtom;
-use evaluate::evaluate_expression;
-use parse::parse_expression;
+//! Random numbers, dice rolls, card draws and more from short expressions,
+//! e.g. "4d6dl1", "1-100", "pick red, green, blue" or "draw 5".
+//!
+//! Lines can be evaluated straight to text, or parsed into an
+//! [`Expression`], inspected, and then evaluated to an [`Outcome`]:
+//!
+//! ```
+//! use rand_api::{parse, DiceExpressionAtom, Expression, IntValue, Outcome, Session};
+//!
+//! let mut session = Session::seeded(1);
+//! println!("{}", session.eval("d20 + 5; coin"));
+//!
+//! let expression = parse("2d6 + 3").unwrap();
+//! assert_eq!(
+//!     expression,
+//!     Expression::DiceExpression(vec![
+//!         DiceExpressionAtom::new(IntValue::Literal(2), 6, false, vec![]),
+//!         DiceExpressionAtom::Constant(3),
+//!     ])
+//! );
+//! let outcome = session.evaluate_expression(expression);
+//! assert!((5..=15).contains(&outcome.total().unwrap()));
+//! ```
 
+use std::fmt::{self, Display};
+
+use nom::{character::complete::space0, combinator::eof, sequence::{delimited, pair}};
 
 mod parse;
 mod evaluate;
 mod dice;
+mod cards;
+mod session;
+mod value;
+mod macros;
+mod describe;
+mod outcome;
+mod interval;
+mod distribution;
+pub mod keywords;
+pub mod help;
+
+pub use cards::{
+    card::{Card, JokerColor, Rank, Suit},
+    deck::{Deck, DeckKind},
+    deck_command::DeckCommand,
+};
+pub use dice::{
+    advantage::AdvantageStatus,
+    atom::DiceExpressionAtom,
+    builder::{Dice, DiceSum},
+    keepdrop::{HighestOrLowest, KeepDrop, KeepOrDrop},
+    roll::{AtomResult, DieRoll},
+    roll_modifier::RollModifier,
+};
+pub use distribution::Distribution;
+pub use interval::Interval;
+pub use macros::Macro;
+pub use outcome::{CoinSide, Evaluation, Outcome};
+pub use parse::{tokenize, ParseError, Token, TokenKind};
+pub use value::IntValue;
+use interval::RangeText;
+use dice::atom::write_atoms;
+use parse::{parse_expression, parse_statements};
+pub use session::{HistoryEntry, Session, Settings};
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Debug, PartialEq)]
 
 pub enum Expression {
+    /// Flip a coin, i.e. "coin"
     CoinFlip,
-    IntRange(i64, i64),
-    FloatRange(f32, f32),
-    DiceExpression(Vec<DiceExpressionAtom>)
+    /// A random integer in the range, e.g. "1-100",
+    /// or "0..100" to leave out the upper end
+    IntRange {
+        min: IntValue,
+        max: IntValue,
+        interval: Interval,
+        /// Only give every so many numbers, counting
+        /// from `min`, e.g. the 5 in "0-100 step 5"
+        step: Option<u64>,
+    },
+    /// A random decimal number in the range, e.g. "0.0-1",
+    /// or "[0.0, 1)" to leave out the upper end
+    FloatRange {
+        min: f64,
+        max: f64,
+        interval: Interval,
+        /// How many decimal places the result has, which is the most any
+        /// number in the range was written with, e.g. 2 for "1.00-2.00"
+        decimals: u32,
+        /// e.g. the 0.25 in "0.0-1 step 0.25"
+        step: Option<f64>,
+    },
+    /// A random number from a non-uniform distribution,
+    /// e.g. "normal(100, 15)" or "poisson(3)"
+    Distribution {
+        distribution: Distribution,
+        /// How many decimal places the parameters were written with.
+        /// Samples are shown with at least 2 places, or more if they were.
+        decimals: u32,
+    },
+    /// Roll the dice and add up the atoms, e.g. "2d6 + str - 1"
+    DiceExpression(Vec<DiceExpressionAtom>),
+    /// Pick one of the named items at random, each
+    /// with the given (relative) weight,
+    /// e.g. "pick common:70, uncommon:25, rare:5"
+    Pick(Vec<(String, f64)>),
+    /// Put the given items in a random order,
+    /// e.g. "shuffle a, b, c, d"
+    Shuffle(Vec<String>),
+    /// Put every integer in the (inclusive) range
+    /// in a random order, e.g. "perm 1-10"
+    Permutation(IntValue, IntValue),
+    /// Pick `count` distinct integers from the (inclusive)
+    /// range, e.g. "sample 6 from 1-49" or, to get the
+    /// numbers in ascending order, "sample 6 from 1-49 sorted"
+    Sample {
+        count: u64,
+        min: IntValue,
+        max: IntValue,
+        sorted: bool,
+    },
+    /// Draw from, shuffle, or otherwise use the current deck of cards
+    Deck(DeckCommand),
+    /// Set a variable to the total of a dice expression,
+    /// e.g. "str = 3" or "hp = 4d8 + 4"
+    Assign {
+        name: String,
+        value: Vec<DiceExpressionAtom>,
+    },
+    /// Save a macro, e.g. "def smite(n) = (n+1)d8"
+    Define(Macro),
+    /// Remove a macro, e.g. "undef smite"
+    Undefine(String),
+    /// List every macro, i.e. "macros"
+    ListMacros,
+    /// Evaluate a macro, e.g. "fireball" or "smite(2)"
+    CallMacro {
+        name: String,
+        args: Vec<IntValue>,
+    },
 }
 
+/// The most decimal places a float is shown with, which is enough for
+/// 17 significant digits of even the smallest f64 (about 5e-324).
+/// It also keeps well under the most places Rust can format, `u16::MAX`.
+pub const MAX_DECIMALS: u32 = 340;
+
+/// Write a float with the given number of decimal places, so that it's
+/// read back as a float with the same places, e.g. "1.50" for 1.5 and 2
+pub(crate) fn float_text(value: f64, decimals: u32) -> String {
+    let decimals = decimals.min(MAX_DECIMALS);
+    if decimals == 0 {
+        // The "." is what makes it a float (see `RangeText` for "5...6")
+        format!("{value:.0}.")
+    } else {
+        format!("{value:.*}", decimals as usize)
+    }
+}
+
+/// Write the expression in a canonical form, which parses back to the same
+/// expression, e.g. "1d20d + 2" for the shorthand "2d" (a d20 with
+/// disadvantage, plus 2), so that it's clear how a line was understood.
+///
+/// That holds for every expression that was parsed, but not for every one
+/// built in code. A dice sum of a lone constant, or of a constant minus
+/// another, has no text of its own ("5" is the d20 shorthand and "3 - 1"
+/// is a range), so it's written as "5" or "3 - 1" all the same.
+impl Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expression::CoinFlip => f.write_str("coin"),
+            Expression::IntRange { min, max, interval, step } => {
+                write!(f, "{}", RangeText { min, max, interval: *interval })?;
+                if let Some(step) = step {
+                    write!(f, " step {step}")?;
+                }
+                Ok(())
+            }
+            Expression::FloatRange { min, max, interval, decimals, step } => {
+                let range = RangeText {
+                    min: float_text(*min, *decimals),
+                    max: float_text(*max, *decimals),
+                    interval: *interval,
+                };
+                write!(f, "{range}")?;
+                if let Some(step) = step {
+                    write!(f, " step {}", float_text(*step, *decimals))?;
+                }
+                Ok(())
+            }
+            Expression::Distribution { distribution, decimals } => {
+                write!(f, "{distribution:.*}", *decimals as usize)
+            }
+            Expression::DiceExpression(atoms) => {
+                // On its own "dex" is a macro call, and "dex - 3" is a
+                // range, so a "+" keeps them as dice expressions
+                let ambiguous = match atoms.as_slice() {
+                    [DiceExpressionAtom::Variable { subtracted: false, .. }] => true,
+                    [DiceExpressionAtom::Variable { subtracted: false, .. }, second] => {
+                        second.is_negative() && !matches!(second, DiceExpressionAtom::Roll { .. })
+                    }
+                    _ => false,
+                };
+                if ambiguous {
+                    f.write_str("+")?;
+                }
+                write_atoms(f, atoms)
+            }
+            Expression::Pick(items) => {
+                f.write_str("pick ")?;
+                for (i, (name, weight)) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    f.write_str(name)?;
+                    // A weight of 1 is the default, so it's left out
+                    if *weight != 1.0 {
+                        write!(f, ":{weight}")?;
+                    }
+                }
+                Ok(())
+            }
+            Expression::Shuffle(items) => write!(f, "shuffle {}", items.join(", ")),
+            Expression::Permutation(min, max) => write!(f, "perm {min}-{max}"),
+            Expression::Sample { count, min, max, sorted } => {
+                write!(f, "sample {count} from {min}-{max}")?;
+                if *sorted {
+                    f.write_str(" sorted")?;
+                }
+                Ok(())
+            }
+            Expression::Deck(command) => write!(f, "{command}"),
+            Expression::Assign { name, value } => {
+                write!(f, "{name} = ")?;
+                write_atoms(f, value)
+            }
+            Expression::Define(definition) => write!(f, "{definition}"),
+            Expression::Undefine(name) => write!(f, "undef {name}"),
+            Expression::ListMacros => f.write_str("macros"),
+            Expression::CallMacro { name, args } => {
+                f.write_str(name)?;
+                if !args.is_empty() {
+                    f.write_str("(")?;
+                    for (i, arg) in args.iter().enumerate() {
+                        if i > 0 {
+                            f.write_str(", ")?;
+                        }
+                        // Arguments are dice expressions already, so they don't need parentheses
+                        match arg {
+                            IntValue::Expression(atoms) => write_atoms(f, atoms)?,
+                            arg => write!(f, "{arg}")?,
+                        }
+                    }
+                    f.write_str(")")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Evaluate a single expression, e.g. "3d6" or "coin".
+/// Nothing is kept between calls (e.g. each "draw" uses
+/// a fresh deck); use a `Session` for that.
 pub fn evaluate(expression: &str) -> String {
-    match parse_expression(expression) {
-        Ok((_remainder, expression)) => evaluate_expression(expression),
-        Err(e) => format!("Error: {e}"),
+    Session::new().eval(expression)
+}
+
+/// Parse a single expression, e.g. "4d6dl1" or "pick a, b".
+/// A line with several expressions (e.g. "d20; d20") is an
+/// error; use `parse_line` for those.
+pub fn parse(expression: &str) -> Result<Expression, ParseError> {
+    delimited(space0, parse_expression, pair(space0, eof))(expression)
+        .map(|(_remainder, expression)| expression)
+        .map_err(|e| ParseError::new(expression, e))
+}
+
+/// Parse a line with one or more expressions, separated by
+/// ";" or ",", e.g. "d20+5; 2d6+3"
+pub fn parse_line(line: &str) -> Result<Vec<Expression>, ParseError> {
+    parse_statements(line)
+        .map(|(_remainder, expressions)| expressions)
+        .map_err(|e| ParseError::new(line, e))
+}
+
+/// Check that a line parses, without evaluating it.
+/// The error says where the line stopped parsing.
+pub fn validate(line: &str) -> Result<(), ParseError> {
+    parse_line(line).map(|_| ())
+}
+
+/// Write a line the way it was understood, e.g. "1d20d + 2" for "2d",
+/// with each expression in the canonical form `Expression` displays
+pub fn normalize(line: &str) -> Result<String, ParseError> {
+    let expressions: Vec<String> = parse_line(line)?.iter().map(ToString::to_string).collect();
+    Ok(expressions.join("; "))
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::{
+        bool::weighted,
+        collection::vec,
+        prelude::{prop_assert_eq, prop_assume, prop_oneof, proptest, Just, ProptestConfig, Strategy},
+        sample::select,
+    };
+
+    use crate::{keywords::DISTRIBUTIONS, normalize, parse, parse_line, Dice, DiceSum, Expression};
+
+    fn parse_one(line: &str) -> Option<Expression> {
+        parse(line).ok()
+    }
+
+    #[test]
+    fn parse_single_expression() {
+        assert_eq!(parse(" coin ").unwrap(), Expression::CoinFlip);
+        assert_eq!(parse("coin; coin").unwrap_err().column, 5);
+        assert_eq!(parse_line("coin; coin").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn canonical_form() {
+        assert_eq!(normalize("-6d6r2dl2dh2+d20adv -5").unwrap(), "-6d6r2dl2dh2 + 1d20a - 5");
+        assert_eq!(normalize("2d").unwrap(), "1d20d + 2");
+        assert_eq!(normalize("4d6k3, (str+1)d8").unwrap(), "4d6kh3; (str + 1)d8");
+        assert_eq!(normalize("pick a:2,b , c:0.5").unwrap(), "pick a:2, b, c:0.5");
+        assert!(normalize("0-1e-70000").is_ok());
+        assert_eq!(normalize("[5., 6.); 5..6.").unwrap(), "[5., 6.); [5., 6.)");
+        assert_eq!(normalize("sample 6 from 1 - 49 sorted").unwrap(), "sample 6 from 1-49 sorted");
+        assert_eq!(normalize("1.5 - 2").unwrap(), "1.5-2.0");
+        assert_eq!(normalize("0 ..= 9; [0, 1.0); 0.1..0.2").unwrap(), "0-9; 0.0..1.0; 0.1..0.2");
+        assert_eq!(normalize("(0,n]; (1.5, 2]").unwrap(), "(0, n]; (1.5, 2.0]");
+        assert_eq!(normalize("1.00 - 2; 0-100  step 5").unwrap(), "1.00-2.00; 0-100 step 5");
+        assert_eq!(normalize("0.0..1 step .25; 5.-6").unwrap(), "0.00..1.00 step 0.25; 5.-6.");
+        assert_eq!(normalize("triangular( 1,5 , 10.5); exponential(.5)").unwrap(), "tri(1.0, 5.0, 10.5); exp(0.5)");
+        assert_eq!(normalize("smite( 2 , lvl)").unwrap(), "smite(2, lvl)");
+        assert_eq!(normalize("draw; deck new custom x,y").unwrap(), "draw 1; deck new custom x, y");
+
+        // Built in code, so it's written the same way even where that reads as something else
+        assert_eq!(Expression::from(Dice::new(4, 6).drop_lowest(1) + 2).to_string(), "4d6dl1 + 2");
+        assert_eq!(Expression::from(DiceSum::from(3) - 1).to_string(), "3 - 1");
+        assert!(matches!(parse("3 - 1").unwrap(), Expression::IntRange { .. }));
+    }
+
+    fn space() -> impl Strategy<Value = &'static str> {
+        select(vec![" ", "", "  "])
+    }
+
+    fn int() -> impl Strategy<Value = String> {
+        (-20i64..=120).prop_map(|int| int.to_string())
+    }
+
+    fn name() -> impl Strategy<Value = String> {
+        select(vec!["str", "dex", "hp_max", "_x1"]).prop_map(str::to_owned)
+    }
+
+    /// A float with 0 to 3 decimal places, e.g. "-3.14" or "5."
+    fn float() -> impl Strategy<Value = String> {
+        (0..4usize, -10.0..10.0f64).prop_map(|(places, float)| match places {
+            0 => format!("{float:.0}."),
+            _ => format!("{float:.places$}"),
+        })
+    }
+
+    /// A roll with any of its modifiers, e.g. "(str + 1)d8", "4d6dl1" or "d20adv"
+    fn roll() -> impl Strategy<Value = String> {
+        let count = prop_oneof![
+            1 => Just(String::new()),
+            1 => (name(), 0..5).prop_map(|(name, plus)| format!("({name} + {plus})")),
+            2 => (0..30u32).prop_map(|count| count.to_string()),
+        ];
+        let modifiers = [
+            "r1", "r2", "k2", "kh1", "kl3", "d1", "dl1", "dh2", "a", "adv", "advantage", "d", "dis", "disadvantage",
+        ];
+        (count, 1..=100, vec(select(modifiers.to_vec()), 0..3))
+            .prop_map(|(count, sides, modifiers)| format!("{count}d{sides}{}", modifiers.concat()))
+    }
+
+    /// Atoms added or subtracted, e.g. "3d6 + str - 1"
+    fn atoms() -> impl Strategy<Value = String> {
+        let atom = prop_oneof![(0..50u32).prop_map(|constant| constant.to_string()), name(), roll()];
+        let sign =
+            (space(), select(vec!["+", "-"]), space()).prop_map(|(before, sign, after)| [before, sign, after].concat());
+        (weighted(0.2), vec((sign, atom), 1..5)).prop_map(|(leading_sign, atoms)| {
+            let mut line = String::new();
+            for (i, (sign, atom)) in atoms.into_iter().enumerate() {
+                if i > 0 || leading_sign {
+                    line.push_str(&sign);
+                }
+                line.push_str(&atom);
+            }
+            line
+        })
+    }
+
+    /// A range written any of the ways ranges can be, maybe with a step, e.g. "[0, 1.5) step 0.25"
+    fn range() -> impl Strategy<Value = String> {
+        let bound = || prop_oneof![float(), int(), name()];
+        let step = prop_oneof![
+            2 => Just(String::new()),
+            1 => (1..10).prop_map(|step| format!(" step {step}")),
+            1 => float().prop_map(|step| format!(" step {step}")),
+        ];
+        let open = select(vec!["[", "("]);
+        let close = select(vec!["]", ")"]);
+        (bound(), bound(), step, 0..4, space(), space(), open, close).prop_map(
+            |(min, max, step, form, before, after, open, close)| match form {
+                0 => format!("{min}{before}..{after}{max}{step}"),
+                1 => format!("{min}-{max}{step}"),
+                2 => format!("{min}..={max}{step}"),
+                _ => format!("{open}{min},{after}{max}{close}"),
+            },
+        )
+    }
+
+    /// A distribution with one to three parameters, e.g. "normal(100, 15)"
+    fn distribution() -> impl Strategy<Value = String> {
+        (select(DISTRIBUTIONS.to_vec()), vec(prop_oneof![float(), int()], 1..4), space())
+            .prop_map(|(name, params, space)| format!("{name}({})", params.join(&format!(",{space}"))))
+    }
+
+    /// A line from the grammar, with random spacing and aliases
+    /// (e.g. "adv" or "a"), which may or may not parse
+    fn line() -> impl Strategy<Value = String> {
+        let deck_commands =
+            vec!["draw", "draw 3", "deck new", "deck new tarot", "deck new jokers", "deck shuffle", "deck remaining"];
+        prop_oneof![
+            1 => Just("coin".to_owned()),
+            1 => (int(), space(), space(), int())
+                .prop_map(|(min, before, after, max)| format!("{min}{before}-{after}{max}")),
+            1 => (float(), prop_oneof![float(), int()]).prop_map(|(min, max)| format!("{min}-{max}")),
+            1 => (int(), space(), select(vec!["a", "d", "adv", "dis", "r1", ""]))
+                .prop_map(|(int, space, modifier)| format!("{int}{space}{modifier}")),
+            1 => (1..9, space()).prop_map(|(weight, space)| format!("pick a:{weight}, b c,{space}d")),
+            1 => space().prop_map(|space| format!("shuffle x,{space}y , z")),
+            1 => (int(), name()).prop_map(|(min, max)| format!("perm {min}-{max}")),
+            1 => (0..9, int()).prop_map(|(count, max)| format!("sample {count} from 1-{max} sorted")),
+            1 => select(deck_commands).prop_map(str::to_owned),
+            1 => (name(), space(), space(), atoms())
+                .prop_map(|(name, before, after, atoms)| format!("{name}{before}={after}{atoms}")),
+            1 => (name(), atoms()).prop_map(|(name, atoms)| format!("def {name}(a, b) = {atoms}")),
+            1 => (name(), atoms(), atoms()).prop_map(|(name, a, b)| format!("{name}({a},{b})")),
+            1 => select(vec!["macros", "undef str"]).prop_map(str::to_owned),
+            1 => range(),
+            1 => distribution(),
+            2 => atoms(),
+        ]
+    }
+
+    proptest! {
+        // Lines that don't parse are rejected, so this also fails if most of them don't
+        #![proptest_config(ProptestConfig { cases: 2_000, max_global_rejects: 2_000, ..ProptestConfig::default() })]
+
+        #[test]
+        fn display_round_trips(line in line()) {
+            let expression = parse_one(&line);
+            prop_assume!(expression.is_some());
+            let canonical = expression.as_ref().unwrap().to_string();
+            prop_assert_eq!(parse_one(&canonical), expression, "{:?} was written as {:?}", line, canonical);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use crate::{parse_line, Evaluation, Expression, Session};
+
+    #[test]
+    fn round_trip() {
+        let line = "4d6dl1 + (str + 1)d8 - 2; pick a:2, b; [0, 1.5) step .25; normal(0, 1); \
+                    sample 3 from 1-10 sorted; deck new custom x, y; def f(n) = (n)d6";
+        let expressions = parse_line(line).unwrap();
+        let json = serde_json::to_string(&expressions).unwrap();
+        assert_eq!(serde_json::from_str::<Vec<Expression>>(&json).unwrap(), expressions);
+
+        let mut session = Session::seeded(7);
+        session.eval("str = 3");
+        let evaluation = session.evaluate("4d6dl1 + str; perm 1-5; 1.5-2; draw 2; coin; nope");
+        let json = serde_json::to_string(&evaluation).unwrap();
+        assert_eq!(serde_json::from_str::<Evaluation>(&json).unwrap(), evaluation);
     }
 }
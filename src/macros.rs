@@ -0,0 +1,42 @@
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use crate::{parse::parse_define, Expression};
+
+/// A named expression saved for later, e.g. "def fireball = 8d6".
+/// Macros can take integer parameters, which are set as variables
+/// while the body is evaluated, e.g. "def smite(n) = (n+1)d8"
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Macro {
+    pub name: String,
+    pub params: Vec<String>,
+    /// The expressions the macro evaluates, e.g. "d20+7 ; 1d8+4"
+    pub body: String,
+}
+
+impl Display for Macro {
+    /// Write the macro the way it is defined, e.g. "def smite(n) = (n+1)d8",
+    /// so that it can be parsed back with `str::parse`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "def {}", self.name)?;
+        if !self.params.is_empty() {
+            write!(f, "({})", self.params.join(", "))?;
+        }
+        write!(f, " = {}", self.body)
+    }
+}
+
+impl FromStr for Macro {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match parse_define(s) {
+            Ok((_, Expression::Define(definition))) => Ok(definition),
+            Ok(_) => unreachable!(),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
@@ -1,13 +1,196 @@
-use rand_api::evaluate;
+mod cli;
+mod repl;
+mod serve;
+
+use std::{
+    env, fs,
+    io::{self, BufRead, IsTerminal},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::{Arc, Mutex},
+};
+
+use cli::{Args, Format};
+use rand_api::{Macro, Session};
 use reedline::{
     default_emacs_keybindings, DefaultPrompt, KeyCode, KeyModifiers, ListMenu, Reedline, ReedlineEvent, ReedlineMenu,
-    Signal, DefaultHinter, Emacs, FileBackedHistory, DefaultPromptSegment
+    Signal, Emacs, FileBackedHistory, DefaultPromptSegment, EditCommand, History, ColumnarMenu
+};
+use repl::{
+    completion::ReplCompleter, highlight::ReplHighlighter, hint::ReplHinter, history::ReplHistory, render::Renderer,
+    validate::ReplValidator,
 };
-use nu_ansi_term::{Color, Style};
 
-fn main() {
-    let history = Box::new(FileBackedHistory::new(20));
+/// How many lines of history are kept, unless the
+/// RAND_API_HISTORY_SIZE environment variable says otherwise
+const DEFAULT_HISTORY_SIZE: usize = 1_000;
+
+/// Where files kept between runs (e.g. macros) are stored,
+/// e.g. ~/.local/share/rand_api on Linux
+fn data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("rand_api"))
+}
+
+/// Add the macros saved in the given file (one definition per line) to the session
+fn load_macros(session: &mut Session, path: &Path) {
+    // There's nothing to load the first time the REPL is run
+    let Ok(contents) = fs::read_to_string(path) else {
+        return;
+    };
+    for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+        match line.parse::<Macro>() {
+            Ok(definition) => session.add_macro(definition),
+            Err(e) => eprintln!("Skipping macro \"{line}\" in {}: {e}", path.display()),
+        }
+    }
+}
+
+fn save_macros(session: &Session, path: &Path) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let contents: String = session.macros().map(|definition| format!("{definition}\n")).collect();
+    fs::write(path, contents)
+}
+
+fn history_size() -> usize {
+    match env::var("RAND_API_HISTORY_SIZE") {
+        Ok(size) => size.parse().unwrap_or_else(|_| {
+            eprintln!("RAND_API_HISTORY_SIZE should be a number of lines, not \"{size}\"");
+            DEFAULT_HISTORY_SIZE
+        }),
+        Err(_) => DEFAULT_HISTORY_SIZE,
+    }
+}
+
+/// History that's saved in the data directory, so that it's kept
+/// between runs. Falls back to history that's only kept in memory.
+/// Reedline doesn't save a line that's the same as the one before it,
+/// so consecutive duplicates are left out as lines are saved. The file
+/// is only ever written by reedline, which locks it while it does.
+fn create_history() -> Box<dyn History> {
+    let capacity = history_size();
+    if let Some(dir) = data_dir() {
+        let path = dir.join("history.txt");
+        let history = fs::create_dir_all(&dir).and_then(|_| FileBackedHistory::with_file(capacity, path.clone()));
+        match history {
+            Ok(history) => return Box::new(ReplHistory::new(history)),
+            Err(e) => eprintln!("Couldn't open the history file {}: {e}", path.display()),
+        }
+    }
+    Box::new(ReplHistory::new(FileBackedHistory::new(capacity)))
+}
+
+/// Output is coloured unless `--no-color` is passed, the NO_COLOR
+/// environment variable is set (see https://no-color.org), or
+/// the output isn't a terminal, e.g. it's piped to a file
+fn use_color(args: &Args) -> bool {
+    let no_color_env = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    !args.no_color && !no_color_env && io::stdout().is_terminal()
+}
+
+/// Evaluate a line and print its output, saving the
+/// macros if the line defined or removed one
+fn eval_and_save(
+    session: &mut Session,
+    line: &str,
+    macros_path: Option<&Path>,
+    format: Format,
+    renderer: &Renderer,
+) -> bool {
+    let macros_before: Vec<Macro> = session.macros().cloned().collect();
+    let succeeded = cli::run_line(session, line, format, renderer);
+    if let Some(path) = macros_path {
+        if session.macros().ne(macros_before.iter()) {
+            if let Err(e) = save_macros(session, path) {
+                eprintln!("Couldn't save macros to {}: {e}", path.display());
+            }
+        }
+    }
+    succeeded
+}
+
+fn main() -> ExitCode {
+    let args = match Args::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return ExitCode::from(2);
+        }
+    };
+    let renderer = Renderer { color: use_color(&args) };
+    // The session is kept between lines so that e.g. cards
+    // stay drawn until the deck is shuffled
+    let mut session = Session::new();
+    let macros_path = data_dir().map(|dir| dir.join("macros.txt"));
+    if let Some(path) = &macros_path {
+        load_macros(&mut session, path);
+    }
+
+    if let Some(address) = &args.serve {
+        return match serve::serve(address, session.macros().cloned().collect()) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("Error: couldn't serve on {address}: {e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    // Expressions given as arguments, in a file, or piped in, are evaluated
+    // without starting the REPL, and any errors give a non-zero exit code
+    let succeeded = if let Some(path) = &args.file {
+        match cli::run_file(&mut session, path, args.format, &renderer) {
+            Ok(succeeded) => succeeded,
+            Err(e) => {
+                eprintln!("Error: couldn't read {}: {e}", path.display());
+                false
+            }
+        }
+    } else if !args.expressions.is_empty() {
+        eval_and_save(&mut session, &args.expressions.join(" "), macros_path.as_deref(), args.format, &renderer)
+    } else if !io::stdin().is_terminal() {
+        // Each line is evaluated, carrying on past errors
+        let mut succeeded = true;
+        for line in io::stdin().lock().lines() {
+            match line {
+                Ok(line) if line.trim().is_empty() => {}
+                Ok(line) => {
+                    succeeded &= eval_and_save(&mut session, &line, macros_path.as_deref(), args.format, &renderer)
+                }
+                Err(e) => {
+                    eprintln!("Error: couldn't read stdin: {e}");
+                    succeeded = false;
+                    break;
+                }
+            }
+        }
+        succeeded
+    } else {
+        run_repl(session, macros_path.as_deref(), &renderer);
+        true
+    };
+    if succeeded {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn run_repl(session: Session, macros_path: Option<&Path>, renderer: &Renderer) {
+    // The session is shared with the completer and hinter,
+    // which need to know the variables and macros
+    let session = Arc::new(Mutex::new(session));
+    let history = create_history();
     let mut keybindings = default_emacs_keybindings();
+    keybindings.add_binding(
+        KeyModifiers::NONE,
+        KeyCode::Tab,
+        ReedlineEvent::UntilFound(vec![
+            ReedlineEvent::Menu("completion".to_owned()),
+            ReedlineEvent::MenuNext,
+        ]),
+    );
     keybindings.add_binding(
         KeyModifiers::CONTROL,
         KeyCode::Char('r'),
@@ -28,9 +211,14 @@ fn main() {
             .with_menu(ReedlineMenu::HistoryMenu(Box::new(
                 ListMenu::default().with_name("history"),
             )))
-            .with_hinter(Box::new(
-                DefaultHinter::default().with_style(Style::new().fg(Color::DarkGray)),
-            ))
+            .with_menu(ReedlineMenu::EngineCompleter(Box::new(
+                ColumnarMenu::default().with_name("completion"),
+            )))
+            .with_completer(Box::new(ReplCompleter::new(Arc::clone(&session))))
+            .with_highlighter(Box::new(ReplHighlighter))
+            .with_hinter(Box::new(ReplHinter::new(Arc::clone(&session))))
+            .with_validator(Box::new(ReplValidator))
+            .with_ansi_colors(renderer.color)
             .with_edit_mode(edit_mode);
     let prompt = DefaultPrompt {
         left_prompt: DefaultPromptSegment::Basic("rand-api".to_owned()),
@@ -41,7 +229,27 @@ fn main() {
         let sig = line_editor.read_line(&prompt);
         match sig {
             Ok(Signal::Success(buffer)) => {
-                println!("{}", evaluate(&buffer));
+                // Lines carried over by the validator are evaluated as one line
+                let line = buffer.replace('\n', " ");
+                // The hint already shows why a line doesn't parse, so
+                // it's put back to be fixed instead of printing an error
+                if !repl::can_run(&line) {
+                    line_editor.run_edit_commands(&[EditCommand::InsertString(buffer)]);
+                    continue;
+                }
+                let mut session = session.lock().unwrap();
+                // "edit <macro>" puts the macro's definition in the
+                // next line, so it can be changed and saved again
+                if let Some(name) = line.trim().strip_prefix("edit ") {
+                    let name = name.trim();
+                    match session.macros().find(|definition| definition.name == name) {
+                        Some(definition) => line_editor.run_edit_commands(&[EditCommand::InsertString(definition.to_string())]),
+                        None => println!("{}", renderer.render_error(&format!("unknown macro \"{name}\""))),
+                    }
+                    continue;
+                }
+
+                eval_and_save(&mut session, &line, macros_path, Format::Text, renderer);
             }
             Ok(Signal::CtrlD) | Ok(Signal::CtrlC) => {
                 println!("\nAborted!");
@@ -0,0 +1,367 @@
+//! What evaluating a line gives, which can be shown as text
+//! (e.g. "2d6 [3, 5] + 1 = 9") or as a JSON document
+
+use std::fmt::{self, Display, Write};
+
+use crate::dice::roll::{AtomResult, DieRoll};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CoinSide {
+    Heads,
+    Tails,
+}
+
+impl Display for CoinSide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoinSide::Heads => f.write_str("Heads"),
+            CoinSide::Tails => f.write_str("Tails"),
+        }
+    }
+}
+
+/// What a single expression evaluated to
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Outcome {
+    Coin(CoinSide),
+    Int(i64),
+    /// A number from a float range, shown with as many
+    /// decimal places as the range was written with
+    Float { value: f64, decimals: u32 },
+    /// Each atom of a dice expression, e.g. the "2d6" and "1" in "2d6+1",
+    /// and their total
+    Dice {
+        atoms: Vec<AtomResult>,
+        total: i64,
+    },
+    /// A dice expression whose total was saved in a variable
+    Assign {
+        name: String,
+        atoms: Vec<AtomResult>,
+        total: i64,
+    },
+    Pick(String),
+    Shuffle(Vec<String>),
+    /// The numbers from "perm" or "sample"
+    Numbers(Vec<i64>),
+    /// The cards drawn from the deck, and whether
+    /// it ran out before all of them could be drawn
+    Cards {
+        cards: Vec<String>,
+        ran_out: bool,
+    },
+    /// What a command that doesn't give a random result did,
+    /// e.g. "Shuffled 52 cards" or "Defined attack"
+    Message(String),
+    /// What each expression in a macro's body evaluated to
+    Macro {
+        name: String,
+        outcomes: Vec<Outcome>,
+    },
+    Error {
+        message: String,
+        /// Where the line stopped parsing, for parse errors
+        column: Option<usize>,
+    },
+}
+
+impl Outcome {
+    pub fn is_error(&self) -> bool {
+        matches!(self, Outcome::Error { .. })
+    }
+
+    /// The total of a dice expression (or an assignment's dice expression)
+    pub fn total(&self) -> Option<i64> {
+        match self {
+            Outcome::Dice { total, .. } | Outcome::Assign { total, .. } => Some(*total),
+            _ => None,
+        }
+    }
+
+    /// A JSON object with a "type" and the outcome's details, e.g.
+    /// `{"type":"int","result":4}`. The keys and their order are
+    /// stable, so the output can be relied on by other programs.
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        match self {
+            Outcome::Coin(side) => {
+                write!(json, r#"{{"type":"coin","result":{}}}"#, json_string(&side.to_string().to_lowercase()))
+            }
+            Outcome::Int(value) => write!(json, r#"{{"type":"int","result":{value}}}"#),
+            // JSON has no infinity or NaN, so they're written as null
+            Outcome::Float { value, .. } if !value.is_finite() => write!(json, r#"{{"type":"float","result":null}}"#),
+            Outcome::Float { value, decimals } => {
+                write!(json, r#"{{"type":"float","result":{value:.*}}}"#, *decimals as usize)
+            }
+            Outcome::Dice { atoms, total } => write!(
+                json,
+                r#"{{"type":"dice","total":{total},"atoms":{}}}"#,
+                json_array(atoms, atom_json)
+            ),
+            Outcome::Assign { name, atoms, total } => write!(
+                json,
+                r#"{{"type":"assign","name":{},"total":{total},"atoms":{}}}"#,
+                json_string(name),
+                json_array(atoms, atom_json)
+            ),
+            Outcome::Pick(item) => write!(json, r#"{{"type":"pick","result":{}}}"#, json_string(item)),
+            Outcome::Shuffle(items) => {
+                write!(json, r#"{{"type":"shuffle","result":{}}}"#, json_array(items, |item| json_string(item)))
+            }
+            Outcome::Numbers(numbers) => {
+                write!(json, r#"{{"type":"numbers","result":{}}}"#, json_array(numbers, i64::to_string))
+            }
+            Outcome::Cards { cards, ran_out } => write!(
+                json,
+                r#"{{"type":"cards","result":{},"ran_out":{ran_out}}}"#,
+                json_array(cards, |card| json_string(card))
+            ),
+            Outcome::Message(message) => write!(json, r#"{{"type":"message","message":{}}}"#, json_string(message)),
+            Outcome::Macro { name, outcomes } => write!(
+                json,
+                r#"{{"type":"macro","name":{},"results":{}}}"#,
+                json_string(name),
+                json_array(outcomes, Outcome::to_json)
+            ),
+            Outcome::Error { message, column } => write!(
+                json,
+                r#"{{"type":"error","message":{},"column":{}}}"#,
+                json_string(message),
+                column.map_or_else(|| "null".to_owned(), |column| column.to_string())
+            ),
+        }
+        .unwrap();
+        json
+    }
+
+    /// Write the outcome the way `Display` does, but with each die written
+    /// by `die`, which is given the die and how many sides it has, e.g. to
+    /// colour natural 20s
+    pub fn to_string_with(&self, die: &impl Fn(&DieRoll, u8) -> String) -> String {
+        let mut text = String::new();
+        match self {
+            Outcome::Dice { atoms, total } => write_atoms(&mut text, atoms, *total, die).unwrap(),
+            Outcome::Assign { name, atoms, total } => {
+                text.push_str(name);
+                text.push_str(" = ");
+                write_atoms(&mut text, atoms, *total, die).unwrap();
+            }
+            Outcome::Macro { outcomes, .. } => {
+                let outcomes: Vec<String> = outcomes.iter().map(|outcome| outcome.to_string_with(die)).collect();
+                text.push_str(&outcomes.join("\n"));
+            }
+            _ => text = self.to_string(),
+        }
+        text
+    }
+}
+
+/// A die as plain text, e.g. "5", or "(5)" if it was dropped
+fn plain_die(die: &DieRoll, _sides: u8) -> String {
+    if die.kept {
+        die.value.to_string()
+    } else {
+        format!("({})", die.value)
+    }
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Coin(side) => write!(f, "{side}"),
+            Outcome::Int(value) => write!(f, "{value}"),
+            Outcome::Float { value, decimals } => write!(f, "{value:.*}", *decimals as usize),
+            Outcome::Dice { atoms, total } => write_atoms(f, atoms, *total, &plain_die),
+            Outcome::Assign { name, atoms, total } => {
+                write!(f, "{name} = ")?;
+                write_atoms(f, atoms, *total, &plain_die)
+            }
+            Outcome::Pick(item) => f.write_str(item),
+            Outcome::Shuffle(items) => f.write_str(&items.join(", ")),
+            Outcome::Numbers(numbers) => {
+                let numbers: Vec<String> = numbers.iter().map(i64::to_string).collect();
+                f.write_str(&numbers.join(", "))
+            }
+            Outcome::Cards { cards, ran_out } => {
+                f.write_str(&cards.join(", "))?;
+                if *ran_out {
+                    write!(f, " (the deck ran out after {} cards)", cards.len())?;
+                }
+                Ok(())
+            }
+            Outcome::Message(message) => f.write_str(message),
+            Outcome::Macro { outcomes, .. } => {
+                let outcomes: Vec<String> = outcomes.iter().map(ToString::to_string).collect();
+                f.write_str(&outcomes.join("\n"))
+            }
+            Outcome::Error { message, .. } => write!(f, "Error: {message}"),
+        }
+    }
+}
+
+/// Show each atom and the total, e.g. "2d6 [3, 5] + str (2) - 1 = 9",
+/// with each die written by `die`. Expressions with only constants
+/// are shown as just the total.
+fn write_atoms(
+    f: &mut impl Write,
+    atoms: &[AtomResult],
+    total: i64,
+    die: &impl Fn(&DieRoll, u8) -> String,
+) -> fmt::Result {
+    if atoms.iter().all(|atom| matches!(atom, AtomResult::Constant(_))) {
+        return write!(f, "{total}");
+    }
+
+    for (i, atom) in atoms.iter().enumerate() {
+        let (negative, part) = match atom {
+            AtomResult::Constant(value) => (*value < 0, value.unsigned_abs().to_string()),
+            AtomResult::Variable { name, value, subtracted } => (*subtracted, format!("{name} ({value})")),
+            AtomResult::Roll { number_of_dice, number_of_sides, dice, subtracted } => {
+                let dice = dice.iter().map(|roll| die(roll, *number_of_sides)).collect::<Vec<_>>().join(", ");
+                (*subtracted, format!("{number_of_dice}d{number_of_sides} [{dice}]"))
+            }
+        };
+        let sign = match (i, negative) {
+            (0, false) => "",
+            (0, true) => "-",
+            (_, false) => " + ",
+            (_, true) => " - ",
+        };
+        write!(f, "{sign}{part}")?;
+    }
+    write!(f, " = {total}")
+}
+
+fn atom_json(atom: &AtomResult) -> String {
+    match atom {
+        AtomResult::Constant(value) => format!(r#"{{"type":"constant","value":{value}}}"#),
+        AtomResult::Variable { name, value, subtracted } => format!(
+            r#"{{"type":"variable","name":{},"value":{value},"subtracted":{subtracted}}}"#,
+            json_string(name)
+        ),
+        AtomResult::Roll { number_of_dice, number_of_sides, dice, subtracted } => format!(
+            r#"{{"type":"roll","number_of_dice":{number_of_dice},"number_of_sides":{number_of_sides},"subtracted":{subtracted},"total":{},"dice":{}}}"#,
+            // A roll's total always fits in an i64, as there are at most 1,000 dice
+            atom.total().unwrap_or_default(),
+            json_array(dice, |die| format!(r#"{{"value":{},"kept":{}}}"#, die.value, die.kept))
+        ),
+    }
+}
+
+/// What a whole line evaluated to, one outcome per expression
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Evaluation {
+    pub input: String,
+    pub outcomes: Vec<Outcome>,
+}
+
+impl Evaluation {
+    /// Whether every expression was parsed and evaluated without an error
+    pub fn is_ok(&self) -> bool {
+        !self.outcomes.iter().any(Outcome::is_error)
+    }
+
+    /// e.g. `{"input":"1-10","ok":true,"results":[{"type":"int","result":4}]}`
+    pub fn to_json(&self) -> String {
+        self.to_json_with(&[])
+    }
+
+    /// The same as `to_json`, with the given fields first, e.g.
+    /// `{"line":3,"input":"1-10",...}` for `[("line", "3".to_owned())]`.
+    /// Each value has to be written as JSON already.
+    pub fn to_json_with(&self, fields: &[(&str, String)]) -> String {
+        let fields: String = fields.iter().map(|(key, value)| format!("{}:{value},", json_string(key))).collect();
+        format!(
+            r#"{{{fields}"input":{},"ok":{},"results":{}}}"#,
+            json_string(&self.input),
+            self.is_ok(),
+            json_array(&self.outcomes, Outcome::to_json)
+        )
+    }
+}
+
+/// Each outcome on its own line
+impl Display for Evaluation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, outcome) in self.outcomes.iter().enumerate() {
+            if i > 0 {
+                f.write_char('\n')?;
+            }
+            write!(f, "{outcome}")?;
+        }
+        Ok(())
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut json = String::with_capacity(text.len() + 2);
+    json.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn json_array<T>(items: &[T], to_json: impl Fn(&T) -> String) -> String {
+    let items: Vec<String> = items.iter().map(to_json).collect();
+    format!("[{}]", items.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dice::roll::{AtomResult, DieRoll},
+        outcome::{CoinSide, Evaluation, Outcome},
+    };
+
+    #[test]
+    fn json() {
+        let dice = Outcome::Dice {
+            atoms: vec![
+                AtomResult::Roll {
+                    number_of_dice: 2,
+                    number_of_sides: 6,
+                    dice: vec![DieRoll { value: 3, kept: true }, DieRoll { value: 1, kept: false }],
+                    subtracted: false,
+                },
+                AtomResult::Constant(-1),
+            ],
+            total: 2,
+        };
+        assert_eq!(dice.to_string(), "2d6 [3, (1)] - 1 = 2");
+        assert_eq!(dice.to_string_with(&|die, sides| format!("{}/{sides}", die.value)), "2d6 [3/6, 1/6] - 1 = 2");
+        assert_eq!(
+            dice.to_json(),
+            r#"{"type":"dice","total":2,"atoms":[{"type":"roll","number_of_dice":2,"number_of_sides":6,"subtracted":false,"total":3,"dice":[{"value":3,"kept":true},{"value":1,"kept":false}]},{"type":"constant","value":-1}]}"#
+        );
+
+        assert_eq!(Outcome::Float { value: 0.5, decimals: 2 }.to_json(), r#"{"type":"float","result":0.50}"#);
+        assert_eq!(Outcome::Float { value: f64::INFINITY, decimals: 2 }.to_json(), r#"{"type":"float","result":null}"#);
+
+        let evaluation = Evaluation {
+            input: "coin; \"x\"".to_owned(),
+            outcomes: vec![
+                Outcome::Coin(CoinSide::Heads),
+                Outcome::Error { message: "can't parse \"x\" at column 7".to_owned(), column: Some(7) },
+            ],
+        };
+        assert!(!evaluation.is_ok());
+        assert!(evaluation.to_json_with(&[("line", "3".to_owned())]).starts_with(r#"{"line":3,"input":"coin; \"x\"","#));
+        assert_eq!(
+            evaluation.to_json(),
+            r#"{"input":"coin; \"x\"","ok":false,"results":[{"type":"coin","result":"heads"},{"type":"error","message":"can't parse \"x\" at column 7","column":7}]}"#
+        );
+    }
+}
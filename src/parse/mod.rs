@@ -8,8 +8,22 @@ mod parse_coin_flip;
 mod parse_numbers;
 #[allow(dead_code)]
 mod parse_ranges;
+mod parse_distribution;
 mod parse_expression;
 mod parse_dice_roll;
 mod parse_whitespace;
+mod parse_pick;
+mod parse_shuffle;
+mod parse_sample;
+mod parse_deck;
+mod parse_variables;
+mod parse_macros;
+mod parse_statements;
+mod parse_error;
+mod tokenize;
 
-pub use parse_expression::parse_expression;
\ No newline at end of file
+pub use parse_macros::parse_define;
+pub use parse_error::ParseError;
+pub use parse_expression::parse_expression;
+pub use parse_statements::parse_statements;
+pub use tokenize::{tokenize, Token, TokenKind};
\ No newline at end of file
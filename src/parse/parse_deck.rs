@@ -0,0 +1,118 @@
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::space1,
+    combinator::{map, opt, value},
+    multi::separated_list1,
+    sequence::{pair, preceded},
+    IResult,
+};
+
+use crate::{
+    cards::{deck::DeckKind, deck_command::DeckCommand},
+    Expression,
+};
+
+use super::parse_numbers::parse_unsigned_integer;
+use super::parse_pick::parse_item_name;
+use super::parse_whitespace::spaced;
+
+/// parse a command that uses the current deck, e.g. "deck new" or "draw 5"
+pub fn parse_deck_command(input: &str) -> IResult<&str, Expression> {
+    let (remain, command) = alt((
+        preceded(
+            pair(tag("deck"), space1),
+            alt((
+                parse_new_deck,
+                value(DeckCommand::Shuffle, tag("shuffle")),
+                value(DeckCommand::Remaining, tag("remaining")),
+                value(DeckCommand::Discard, tag("discard")),
+            )),
+        ),
+        parse_draw,
+    ))(input)?;
+
+    Ok((remain, Expression::Deck(command)))
+}
+
+/// parse "deck new", optionally followed by "jokers",
+/// "tarot", or "custom" and a list of card names,
+/// e.g. "deck new custom Goblin, Orc, Troll"
+fn parse_new_deck(input: &str) -> IResult<&str, DeckCommand> {
+    let (remain, kind) = preceded(
+        tag("new"),
+        opt(preceded(
+            space1,
+            alt((
+                value(DeckKind::Standard { jokers: true }, tag("jokers")),
+                value(DeckKind::Tarot, tag("tarot")),
+                map(
+                    preceded(
+                        pair(tag("custom"), space1),
+                        separated_list1(spaced(tag(",")), parse_item_name),
+                    ),
+                    DeckKind::Custom,
+                ),
+            )),
+        )),
+    )(input)?;
+
+    let kind = kind.unwrap_or(DeckKind::Standard { jokers: false });
+
+    Ok((remain, DeckCommand::New(kind)))
+}
+
+/// parse "draw", optionally followed by how many cards to draw (default 1)
+fn parse_draw(input: &str) -> IResult<&str, DeckCommand> {
+    let (remain, amount) = preceded(
+        tag("draw"),
+        opt(preceded(space1, parse_unsigned_integer::<usize>)),
+    )(input)?;
+
+    Ok((remain, DeckCommand::Draw(amount.unwrap_or(1))))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cards::{deck::DeckKind, deck_command::DeckCommand},
+        parse::parse_deck::parse_deck_command,
+        Expression,
+    };
+
+    #[test]
+    fn test_new_deck() {
+        assert_eq!(
+            parse_deck_command("deck new"),
+            Ok(("", Expression::Deck(DeckCommand::New(DeckKind::Standard { jokers: false }))))
+        );
+        assert_eq!(
+            parse_deck_command("deck new jokers"),
+            Ok(("", Expression::Deck(DeckCommand::New(DeckKind::Standard { jokers: true }))))
+        );
+        assert_eq!(
+            parse_deck_command("deck new tarot"),
+            Ok(("", Expression::Deck(DeckCommand::New(DeckKind::Tarot))))
+        );
+        assert_eq!(
+            parse_deck_command("deck new custom Goblin, Orc"),
+            Ok((
+                "",
+                Expression::Deck(DeckCommand::New(DeckKind::Custom(vec![
+                    "Goblin".to_owned(),
+                    "Orc".to_owned()
+                ])))
+            ))
+        );
+    }
+
+    #[test]
+    fn test_deck_commands() {
+        assert_eq!(parse_deck_command("draw"), Ok(("", Expression::Deck(DeckCommand::Draw(1)))));
+        assert_eq!(parse_deck_command("draw 5"), Ok(("", Expression::Deck(DeckCommand::Draw(5)))));
+        assert_eq!(parse_deck_command("deck shuffle"), Ok(("", Expression::Deck(DeckCommand::Shuffle))));
+        assert_eq!(parse_deck_command("deck remaining"), Ok(("", Expression::Deck(DeckCommand::Remaining))));
+        assert_eq!(parse_deck_command("deck discard"), Ok(("", Expression::Deck(DeckCommand::Discard))));
+        assert!(parse_deck_command("deck").is_err());
+    }
+}
@@ -4,9 +4,9 @@ use nom::{
     branch::alt,
     bytes::complete::tag,
     character::complete::space0,
-    combinator::{eof, opt, recognize},
+    combinator::{map, opt, recognize},
     multi::{many0, many1},
-    sequence::{pair, preceded, tuple},
+    sequence::{delimited, pair, preceded, tuple},
     IResult,
 };
 
@@ -16,10 +16,13 @@ use crate::{
         keepdrop::{HighestOrLowest, KeepDrop, KeepOrDrop},
         roll_modifier::RollModifier,
     },
+    value::IntValue,
     Expression,
 };
 
 use super::parse_numbers::{parse_signed_integer, parse_unsigned_integer};
+use super::parse_statements::parse_end_of_expression;
+use super::parse_variables::parse_identifier;
 
 pub fn parse_dice_expression(input: &str) -> IResult<&str, Expression> {
     alt((
@@ -31,23 +34,28 @@ pub fn parse_dice_expression(input: &str) -> IResult<&str, Expression> {
 }
 
 fn parse_regular_dice_expression(input: &str) -> IResult<&str, Expression> {
-    let (remain, atoms) = many1(parse_dice_expression_atom)(input)?;
+    let (remain, atoms) = parse_dice_atoms(input)?;
 
     Ok((remain, Expression::DiceExpression(atoms)))
 }
 
+/// parse one or more atoms, e.g. "3d8 + 2d6r1 + 8"
+pub fn parse_dice_atoms(input: &str) -> IResult<&str, Vec<DiceExpressionAtom>> {
+    many1(parse_dice_expression_atom)(input)
+}
+
 /// parse d20 shorthand, e.g. +5 for 1d20+5
 /// You can't combine multiple roles with this syntax
 fn parse_shorthand_dice_expression(input: &str) -> IResult<&str, Expression> {
     let (remain, (constant, _, modifiers, _, _)) = tuple((
         parse_signed_integer,
         space0,
-        many0(parse_roll_modifier),
+        many0(parse_shorthand_roll_modifier),
         space0,
-        eof,
+        parse_end_of_expression,
     ))(input)?;
 
-    let d20roll = DiceExpressionAtom::new(1, 20, false, modifiers);
+    let d20roll = DiceExpressionAtom::new(1.into(), 20, false, modifiers);
 
     let constant_modifier = DiceExpressionAtom::Constant(constant);
 
@@ -60,15 +68,39 @@ fn parse_dice_expression_atom(input: &str) -> IResult<&str, DiceExpressionAtom>
     preceded(
         // Optional leading whitespace
         space0,
-        alt((parse_dice_roll_atom, parse_constant_atom)),
+        alt((parse_dice_roll_atom, parse_constant_atom, parse_variable_atom)),
     )(input)
 }
 
 fn parse_constant_atom(input: &str) -> IResult<&str, DiceExpressionAtom> {
-    let (remain, constant) = parse_signed_integer::<i8>(input)?;
+    let (remain, constant) = parse_signed_integer::<i64>(input)?;
     Ok((remain, DiceExpressionAtom::Constant(constant)))
 }
 
+fn parse_variable_atom(input: &str) -> IResult<&str, DiceExpressionAtom> {
+    let (remain, (sign, _, name)) = tuple((
+        opt(alt((tag("+"), tag("-")))),
+        space0,
+        parse_identifier,
+    ))(input)?;
+    let subtracted = sign == Some("-");
+
+    Ok((remain, DiceExpressionAtom::Variable { name, subtracted }))
+}
+
+/// parse the number of dice in a roll, which is either
+/// an integer (the "3" in "3d6") or a dice expression in
+/// parentheses (the "(n+1)" in "(n+1)d6")
+pub fn parse_number_of_dice(input: &str) -> IResult<&str, IntValue> {
+    alt((
+        map(parse_unsigned_integer::<i64>, IntValue::Literal),
+        map(
+            delimited(tag("("), parse_dice_atoms, pair(space0, tag(")"))),
+            IntValue::Expression,
+        ),
+    ))(input)
+}
+
 fn parse_dice_roll_atom(input: &str) -> IResult<&str, DiceExpressionAtom> {
     let (remain, ((sign, _, number_of_dice, _, number_of_sides), modifiers)) = pair(
         // First: quantity and number of sides, e.g. 3d6 (required)
@@ -78,7 +110,7 @@ fn parse_dice_roll_atom(input: &str) -> IResult<&str, DiceExpressionAtom> {
             opt(alt((tag("+"), tag("-")))),
             space0,
             // Optional number of dice (defaults to 1)
-            opt(parse_unsigned_integer::<u8>),
+            opt(parse_number_of_dice),
             tag("d"),
             // Number of sides on each dice
             parse_unsigned_integer::<u8>,
@@ -86,7 +118,7 @@ fn parse_dice_roll_atom(input: &str) -> IResult<&str, DiceExpressionAtom> {
         // Second: parse modifiers (rerolls, advantage, etc) -- optional
         many0(parse_roll_modifier),
     )(input)?;
-    let number_of_dice = number_of_dice.unwrap_or(1);
+    let number_of_dice = number_of_dice.unwrap_or(IntValue::Literal(1));
     let subtracted = match sign {
         Some("-") => true,
         // If plus sign, or sign omitted, then the roll is not subtracted
@@ -99,7 +131,7 @@ fn parse_dice_roll_atom(input: &str) -> IResult<&str, DiceExpressionAtom> {
     ))
 }
 
-fn parse_roll_modifier(input: &str) -> IResult<&str, RollModifier> {
+pub fn parse_roll_modifier(input: &str) -> IResult<&str, RollModifier> {
     alt((
         parse_reroll_modifier,
         parse_keep_drop,
@@ -108,6 +140,16 @@ fn parse_roll_modifier(input: &str) -> IResult<&str, RollModifier> {
     ))(input)
 }
 
+/// Keeping or dropping dice makes no sense with a single d20, and
+/// allowing it would e.g. read "3d6" as 1d20 drop lowest 6, plus 3
+fn parse_shorthand_roll_modifier(input: &str) -> IResult<&str, RollModifier> {
+    alt((
+        parse_reroll_modifier,
+        parse_advantage_modifier,
+        parse_disadvantage_modifier,
+    ))(input)
+}
+
 fn parse_reroll_modifier(input: &str) -> IResult<&str, RollModifier> {
     let (remain, (_, sides)) = pair(tag("r"), parse_unsigned_integer::<u8>)(input)?;
 
@@ -175,6 +217,7 @@ mod tests {
         dice::{advantage::*, keepdrop::*},
         parse::parse_dice_roll::*,
         DiceExpressionAtom::*,
+        value::IntValue,
     };
     #[test]
     fn constant() {
@@ -196,7 +239,7 @@ mod tests {
                 "",
                 Expression::DiceExpression(vec![
                     Roll {
-                        number_of_dice: 6,
+                        number_of_dice: IntValue::Literal(6),
                         number_of_sides: 6,
                         advantage_status: AdvantageStatus::None,
                         keep_drop: vec![
@@ -215,7 +258,7 @@ mod tests {
                         subtracted: true
                     },
                     Roll {
-                        number_of_dice: 1,
+                        number_of_dice: IntValue::Literal(1),
                         number_of_sides: 20,
                         advantage_status: AdvantageStatus::Advantage,
                         keep_drop: vec![],
@@ -236,7 +279,7 @@ mod tests {
                 "",
                 Expression::DiceExpression(vec![
                     Roll {
-                        number_of_dice: 6,
+                        number_of_dice: IntValue::Literal(6),
                         number_of_sides: 6,
                         advantage_status: AdvantageStatus::None,
                         keep_drop: vec![
@@ -255,7 +298,7 @@ mod tests {
                         subtracted: true
                     },
                     Roll {
-                        number_of_dice: 1,
+                        number_of_dice: IntValue::Literal(1),
                         number_of_sides: 20,
                         advantage_status: AdvantageStatus::Advantage,
                         keep_drop: vec![],
@@ -275,7 +318,7 @@ mod tests {
             Ok((
                 "",
                 Expression::DiceExpression(vec![Roll {
-                    number_of_dice: 1,
+                    number_of_dice: IntValue::Literal(1),
                     number_of_sides: 20,
                     advantage_status: AdvantageStatus::Advantage,
                     keep_drop: vec![],
@@ -293,7 +336,7 @@ mod tests {
             Ok((
                 "",
                 Expression::DiceExpression(vec![Roll {
-                    number_of_dice: 1,
+                    number_of_dice: IntValue::Literal(1),
                     number_of_sides: 20,
                     advantage_status: AdvantageStatus::Disadvantage,
                     keep_drop: vec![],
@@ -303,4 +346,65 @@ mod tests {
             ))
         )
     }
+
+    #[test]
+    fn regular_roll_is_not_shorthand() {
+        assert_eq!(
+            parse_dice_expression("3d6"),
+            Ok((
+                "",
+                Expression::DiceExpression(vec![Roll {
+                    number_of_dice: IntValue::Literal(3),
+                    number_of_sides: 6,
+                    advantage_status: AdvantageStatus::None,
+                    keep_drop: vec![],
+                    reroll: None,
+                    subtracted: false
+                }])
+            ))
+        )
+    }
+
+    #[test]
+    fn variables() {
+        assert_eq!(
+            parse_dice_expression("d20 + str - cover"),
+            Ok((
+                "",
+                Expression::DiceExpression(vec![
+                    Roll {
+                        number_of_dice: IntValue::Literal(1),
+                        number_of_sides: 20,
+                        advantage_status: AdvantageStatus::None,
+                        keep_drop: vec![],
+                        reroll: None,
+                        subtracted: false
+                    },
+                    Variable { name: "str".to_owned(), subtracted: false },
+                    Variable { name: "cover".to_owned(), subtracted: true }
+                ])
+            ))
+        )
+    }
+
+    #[test]
+    fn computed_number_of_dice() {
+        assert_eq!(
+            parse_dice_roll_atom("(n + 1)d8"),
+            Ok((
+                "",
+                Roll {
+                    number_of_dice: IntValue::Expression(vec![
+                        Variable { name: "n".to_owned(), subtracted: false },
+                        Constant(1)
+                    ]),
+                    number_of_sides: 8,
+                    advantage_status: AdvantageStatus::None,
+                    keep_drop: vec![],
+                    reroll: None,
+                    subtracted: false
+                }
+            ))
+        )
+    }
 }
@@ -0,0 +1,70 @@
+use nom::{
+    bytes::complete::tag,
+    character::complete::{char, space0},
+    combinator::{cut, map_opt, verify},
+    multi::separated_list1,
+    sequence::{pair, preceded, terminated},
+    IResult,
+};
+
+use crate::{distribution::Distribution, keywords::DISTRIBUTIONS, Expression};
+
+use super::parse_numbers::{flexible_parse_f64, with_places};
+use super::parse_variables::parse_identifier;
+use super::parse_whitespace::spaced;
+
+/// parse a sample from a distribution, e.g. "normal(100, 15)" or "tri(1, 5, 10)".
+/// Once the name and "(" are found, the parameters have to be right, rather
+/// than the line being tried as a macro call
+pub fn parse_distribution(input: &str) -> IResult<&str, Expression> {
+    let (remain, name) = terminated(
+        verify(parse_identifier, |name: &str| DISTRIBUTIONS.contains(&name)),
+        pair(space0, char('(')),
+    )(input)?;
+
+    cut(map_opt(
+        terminated(
+            preceded(space0, separated_list1(spaced(tag(",")), with_places(flexible_parse_f64))),
+            pair(space0, char(')')),
+        ),
+        move |params: Vec<(f64, u32)>| {
+            let values: Vec<f64> = params.iter().map(|(value, _)| *value).collect();
+            Some(Expression::Distribution {
+                distribution: Distribution::new(&name, &values)?,
+                decimals: params.iter().map(|(_, places)| *places).max().unwrap_or(0),
+            })
+        },
+    ))(remain)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{distribution::Distribution, parse::parse_distribution::parse_distribution, Expression};
+
+    #[test]
+    fn test_distribution() {
+        assert_eq!(
+            parse_distribution("normal( 100 , 15.5 )"),
+            Ok((
+                "",
+                Expression::Distribution {
+                    distribution: Distribution::Normal { mean: 100.0, std_dev: 15.5 },
+                    decimals: 1
+                }
+            ))
+        );
+        assert_eq!(
+            parse_distribution("triangular(1, 5, 10)"),
+            Ok((
+                "",
+                Expression::Distribution {
+                    distribution: Distribution::Triangular { min: 1.0, mode: 5.0, max: 10.0 },
+                    decimals: 0
+                }
+            ))
+        );
+        assert!(matches!(parse_distribution("tri(1, 5)"), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_distribution("exp + 1"), Err(nom::Err::Error(_))));
+        assert!(parse_distribution("smite(2)").is_err());
+    }
+}
@@ -0,0 +1,37 @@
+use std::fmt;
+
+/// Where and why a line couldn't be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParseError {
+    /// e.g. `can't parse "?" at column 5`
+    pub message: String,
+    /// The column (counting characters from 1)
+    /// where the line stopped parsing
+    pub column: usize,
+}
+
+impl ParseError {
+    pub fn new(line: &str, error: nom::Err<nom::error::Error<&str>>) -> Self {
+        let rest = match error {
+            nom::Err::Error(e) | nom::Err::Failure(e) => e.input,
+            nom::Err::Incomplete(_) => "",
+        };
+        let position = line.len() - rest.len();
+        let column = line[..position].chars().count() + 1;
+        let message = if rest.trim().is_empty() {
+            format!("unexpected end of input at column {column}")
+        } else {
+            format!("can't parse \"{}\" at column {column}", rest.trim_end())
+        };
+        ParseError { message, column }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
@@ -1,17 +1,35 @@
-use nom::{IResult, branch::alt};
+use nom::{IResult, branch::alt, sequence::terminated};
 
 use crate::Expression;
 
-use super::{parse_ranges::{parse_float_range, parse_int_range}, parse_coin_flip::parse_coin_flip, parse_dice_roll::parse_dice_expression};
+use super::{parse_ranges::parse_stepped_range, parse_distribution::parse_distribution, parse_coin_flip::parse_coin_flip, parse_dice_roll::parse_dice_expression, parse_pick::parse_pick, parse_shuffle::{parse_permutation, parse_shuffle}, parse_sample::parse_sample, parse_deck::parse_deck_command, parse_variables::parse_assignment, parse_macros::{parse_define, parse_undefine, parse_list_macros, parse_macro_call}, parse_statements::parse_end_of_expression};
 
 pub fn parse_expression(input: &str) -> IResult<&str, Expression> {
     alt((
-        // there is a separate float range and int range
-        // because if the input is 1-5, we don't want floats
-        // in that range, only integers
-        parse_float_range,
-        parse_int_range,
+        // this needs to be tested first, as e.g. "hp = 1"
+        // would otherwise be parsed as the variable "hp"
+        parse_assignment,
+        // a range is a float range if either end is written as
+        // a float, because if the input is 1-5, we don't want
+        // floats in that range, only integers.
+        // A range has to be the whole expression, so that e.g.
+        // "5 - 1d6" is left to be parsed as a dice expression
+        terminated(parse_stepped_range, parse_end_of_expression),
+        // needs to be tested before macro calls, as e.g.
+        // "normal(100, 15)" would otherwise be parsed as one
+        parse_distribution,
         parse_coin_flip,
+        parse_pick,
+        parse_shuffle,
+        parse_permutation,
+        parse_sample,
+        parse_deck_command,
+        parse_define,
+        parse_undefine,
+        parse_list_macros,
+        // needs to be tested before dice expressions, as
+        // e.g. "fireball" would otherwise be parsed as a variable
+        parse_macro_call,
         parse_dice_expression
     ))
     (input)
@@ -0,0 +1,149 @@
+use nom::{
+    bytes::complete::tag,
+    character::complete::space1,
+    combinator::{map, opt, rest, value, verify},
+    multi::separated_list0,
+    sequence::{delimited, pair, preceded, terminated, tuple},
+    IResult,
+};
+
+use crate::{macros::Macro, value::IntValue, Expression};
+
+use super::parse_dice_roll::parse_dice_atoms;
+use super::parse_statements::parse_end_of_expression;
+use super::parse_variables::parse_identifier;
+use super::parse_whitespace::spaced;
+
+/// parse a macro definition, e.g. "def fireball = 8d6"
+/// or "def smite(n) = (n+1)d8". The body is the rest of the line,
+/// so it can have several expressions, e.g. "def attack = d20+7; 1d8+4"
+pub fn parse_define(input: &str) -> IResult<&str, Expression> {
+    let (remain, (name, params, _, body)) = preceded(
+        pair(tag("def"), space1),
+        tuple((
+            parse_identifier,
+            opt(delimited(
+                spaced(tag("(")),
+                separated_list0(spaced(tag(",")), parse_identifier),
+                spaced(tag(")")),
+            )),
+            spaced(tag("=")),
+            verify(rest, |body: &str| !body.trim().is_empty()),
+        )),
+    )(input)?;
+
+    Ok((
+        remain,
+        Expression::Define(Macro {
+            name,
+            params: params.unwrap_or_default(),
+            body: body.trim().to_owned(),
+        }),
+    ))
+}
+
+/// parse removing a macro, e.g. "undef fireball"
+pub fn parse_undefine(input: &str) -> IResult<&str, Expression> {
+    map(preceded(pair(tag("undef"), space1), parse_identifier), Expression::Undefine)(input)
+}
+
+/// parse the "macros" command, which lists every macro
+pub fn parse_list_macros(input: &str) -> IResult<&str, Expression> {
+    value(Expression::ListMacros, tag("macros"))(input)
+}
+
+/// parse using a macro, e.g. "fireball" or "smite(2)".
+/// This has to be the whole expression, so that e.g. "str + 1"
+/// is left to be parsed as a dice expression
+pub fn parse_macro_call(input: &str) -> IResult<&str, Expression> {
+    let (remain, (name, args)) = terminated(
+        pair(
+            parse_identifier,
+            opt(delimited(
+                spaced(tag("(")),
+                separated_list0(spaced(tag(",")), map(parse_dice_atoms, IntValue::Expression)),
+                spaced(tag(")")),
+            )),
+        ),
+        parse_end_of_expression,
+    )(input)?;
+
+    Ok((
+        remain,
+        Expression::CallMacro {
+            name,
+            args: args.unwrap_or_default(),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dice::atom::DiceExpressionAtom::*,
+        macros::Macro,
+        parse::parse_macros::{parse_define, parse_macro_call},
+        value::IntValue,
+        Expression,
+    };
+
+    #[test]
+    fn test_define() {
+        assert_eq!(
+            parse_define("def fireball = 8d6"),
+            Ok((
+                "",
+                Expression::Define(Macro {
+                    name: "fireball".to_owned(),
+                    params: vec![],
+                    body: "8d6".to_owned()
+                })
+            ))
+        );
+        assert_eq!(
+            parse_define("def smite( n, m ) = (n+1)d8 ; d20"),
+            Ok((
+                "",
+                Expression::Define(Macro {
+                    name: "smite".to_owned(),
+                    params: vec!["n".to_owned(), "m".to_owned()],
+                    body: "(n+1)d8 ; d20".to_owned()
+                })
+            ))
+        );
+        assert!(parse_define("def fireball = ").is_err());
+        assert!(parse_define("def d6 = 8d6").is_err());
+    }
+
+    #[test]
+    fn test_macro_call() {
+        assert_eq!(
+            parse_macro_call("fireball"),
+            Ok(("", Expression::CallMacro { name: "fireball".to_owned(), args: vec![] }))
+        );
+        assert_eq!(
+            parse_macro_call("smite(2, lvl)"),
+            Ok((
+                "",
+                Expression::CallMacro {
+                    name: "smite".to_owned(),
+                    args: vec![
+                        IntValue::Expression(vec![Constant(2)]),
+                        IntValue::Expression(vec![Variable { name: "lvl".to_owned(), subtracted: false }])
+                    ]
+                }
+            ))
+        );
+        assert!(parse_macro_call("str + 1").is_err());
+        assert_eq!(
+            parse_macro_call("fireball; d20"),
+            Ok(("; d20", Expression::CallMacro { name: "fireball".to_owned(), args: vec![] }))
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let definition = "def smite(n, m) = (n+1)d8 ; d20";
+        assert_eq!(definition.parse::<Macro>().unwrap().to_string(), definition);
+    }
+}
@@ -4,11 +4,13 @@ use nom::{
     branch::alt,
     bytes::complete::{tag, take_while, take_while1},
     character::complete::char,
-    combinator::{opt, recognize, map_res},
+    combinator::{consumed, map, opt, recognize, map_res, verify},
     sequence::{pair, tuple},
     IResult, character::complete::space0,
 };
 
+use crate::MAX_DECIMALS;
+
 /// Tell if char is digit or underscore
 /// so numbers can have underscores in them
 /// e.g. 1_000_000
@@ -16,13 +18,19 @@ fn is_digit(c: char) -> bool {
     c.is_ascii_digit() || c == '_'
 }
 
+/// Remove the underscores from a number so
+/// that Rust's parse functions accept it
+fn without_underscores(s: &str) -> String {
+    s.replace('_', "")
+}
+
 /// parse 0 or more digits (including underscore)
 fn parse_digit0(input: &str) -> IResult<&str, &str> {
     take_while(is_digit)(input)
 }
 
 /// parse 1 or more digits (including underscore)
-fn parse_digit1(input: &str) -> IResult<&str, &str> {
+pub fn parse_digit1(input: &str) -> IResult<&str, &str> {
     take_while1(is_digit)(input)
 }
 
@@ -41,7 +49,7 @@ pub fn parse_signed_integer<T>(input: &str) -> IResult<&str, T>
 where T: FromStr + std::ops::Neg<Output = T>, <T as FromStr>::Err: std::fmt::Debug {
     let (input, sign) = opt(alt((char('+'), char('-'))))(input)?;
     let (input, _) = space0(input)?;
-    let (input, value) = map_res(parse_digit1, |s| s.parse::<T>())(input)?;
+    let (input, value) = map_res(parse_digit1, |s| without_underscores(s).parse::<T>())(input)?;
 
     let value = match sign {
         Some('-') => -value,
@@ -52,7 +60,7 @@ where T: FromStr + std::ops::Neg<Output = T>, <T as FromStr>::Err: std::fmt::Deb
 }
 
 pub fn parse_unsigned_integer<T: FromStr>(input: &str) -> IResult<&str, T> {
-    map_res(parse_digit1, |s| s.parse::<T>())(input)
+    map_res(parse_digit1, |s| without_underscores(s).parse::<T>())(input)
 }
 
 /// parse the function part of a float
@@ -72,7 +80,7 @@ fn parse_exponent(input: &str) -> IResult<&str, &str> {
 
 /// parse a float, returning the part of the str that has the float
 /// must contain decimal point and/or exponent
-fn parse_float(input: &str) -> IResult<&str, &str> {
+pub fn parse_float(input: &str) -> IResult<&str, &str> {
     recognize(tuple((
         opt(alt((tag("+"), tag("-")))),
         parse_digit0,
@@ -83,45 +91,99 @@ fn parse_float(input: &str) -> IResult<&str, &str> {
     )))(input)
 }
 
-/// strictly parse f32: must have dot and/or exponent
-pub fn parse_f32(input: &str) -> IResult<&str, f32> {
-    map_res(parse_float, |s| s.parse())(input)
+/// strictly parse f64: must have dot and/or exponent
+pub fn parse_f64(input: &str) -> IResult<&str, f64> {
+    map_res(parse_float, |s| without_underscores(s).parse())(input)
+}
+
+/// How many decimal places a float was written with, e.g. 2 for "1.00"
+/// and 3 for "1.5e-2", so that it can be shown the same way. That's
+/// at most `MAX_DECIMALS`, e.g. for "1e-70000".
+pub fn decimal_places(float: &str) -> u32 {
+    let float = without_underscores(float);
+    let (mantissa, exponent) = match float.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa.to_owned(), exponent.parse::<i64>().unwrap_or(0)),
+        None => (float, 0),
+    };
+    let fraction_digits = mantissa.split_once('.').map_or(0, |(_, fraction)| fraction.len() as i64);
+    fraction_digits.saturating_sub(exponent).clamp(0, MAX_DECIMALS as i64) as u32
+}
+
+/// Wrap a float parser so that it also gives how
+/// many decimal places the float was written with
+pub fn with_places<'a>(
+    parser: impl FnMut(&'a str) -> IResult<&'a str, f64>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, (f64, u32)> {
+    map(consumed(parser), |(text, value)| (value, decimal_places(text)))
 }
 
-/// flexibly parse f32: i.e., the input can be an integer
+/// strictly parse f64, but not one ending in a dot,
+/// so that the "0" in "0..1" is left as an integer
+pub fn parse_f64_before_dots(input: &str) -> IResult<&str, f64> {
+    map_res(
+        verify(parse_float, |s: &str| !s.ends_with('.')),
+        |s| without_underscores(s).parse()
+    )(input)
+}
+
+/// flexibly parse f64: i.e., the input can be an integer
 /// w/o decimal or exponent
-/// and it will be cast to f32
-pub fn flexible_parse_f32(input: &str) -> IResult<&str, f32> {
+/// and it will be cast to f64
+pub fn flexible_parse_f64(input: &str) -> IResult<&str, f64> {
     map_res(
         alt((parse_float, parse_signed_integer_raw)),
-        |s| s.parse::<f32>()
+        |s| without_underscores(s).parse::<f64>()
     )(input)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::parse::parse_numbers::{parse_f32, parse_signed_integer, parse_unsigned_integer};
+    use crate::parse::parse_numbers::{
+        decimal_places, parse_f64, parse_f64_before_dots, parse_signed_integer, parse_unsigned_integer,
+    };
 
     #[test]
     fn test_signed_int() {
         assert_eq!(parse_signed_integer::<i8>("5"), Ok(("", 5i8)));
         assert_eq!(parse_signed_integer::<i8>("+5"), Ok(("", 5i8)));
+        assert_eq!(parse_signed_integer::<i64>("-1_000_000"), Ok(("", -1_000_000i64)));
     }
 
     #[test]
     fn test_unsigned_int() {
         assert_eq!(parse_unsigned_integer::<u8>("5"), Ok(("", 5u8)));
         assert!(parse_unsigned_integer::<u8>("+5").is_err());
+        assert_eq!(parse_unsigned_integer::<u64>("1_000"), Ok(("", 1000u64)));
     }
 
     #[test]
     fn test_float() {
-        assert_eq!(parse_f32("5.0"), Ok(("", 5.0)));
-        assert_eq!(parse_f32("5."), Ok(("", 5.0)));
-        assert_eq!(parse_f32("5e1"), Ok(("", 50.0)));
-        assert_eq!(parse_f32("+0052.0052e-1"), Ok(("", 52.0052e-1)));
-        assert_eq!(parse_f32(".5"), Ok(("", 0.5)));
-        assert_eq!(parse_f32(".5e+0"), Ok(("", 0.5)));
-        assert_eq!(parse_f32("+.5e+0"), Ok(("", 0.5)));
+        assert_eq!(parse_f64("5.0"), Ok(("", 5.0)));
+        assert_eq!(parse_f64("5."), Ok(("", 5.0)));
+        assert_eq!(parse_f64("5e1"), Ok(("", 50.0)));
+        assert_eq!(parse_f64("+0052.0052e-1"), Ok(("", 52.0052e-1)));
+        assert_eq!(parse_f64(".5"), Ok(("", 0.5)));
+        assert_eq!(parse_f64(".5e+0"), Ok(("", 0.5)));
+        assert_eq!(parse_f64("+.5e+0"), Ok(("", 0.5)));
+        assert_eq!(parse_f64("0.1"), Ok(("", 0.1)));
+    }
+
+    #[test]
+    fn test_decimal_places() {
+        assert_eq!(decimal_places("1.00"), 2);
+        assert_eq!(decimal_places("-0.5"), 1);
+        assert_eq!(decimal_places("5."), 0);
+        assert_eq!(decimal_places("1.5e-2"), 3);
+        assert_eq!(decimal_places("1.5E2"), 0);
+        assert_eq!(decimal_places("1_000.000_1"), 4);
+        assert_eq!(decimal_places("1e-70000"), 340);
+        assert_eq!(decimal_places("1e-9223372036854775808"), 340);
+    }
+
+    #[test]
+    fn test_float_before_dots() {
+        assert_eq!(parse_f64_before_dots("0.5..1"), Ok(("..1", 0.5)));
+        assert_eq!(parse_f64_before_dots("1e2..3"), Ok(("..3", 100.0)));
+        assert!(parse_f64_before_dots("0..1").is_err());
     }
 }
@@ -0,0 +1,97 @@
+use nom::{
+    bytes::complete::{tag, take_while1},
+    character::complete::{space0, space1},
+    combinator::{map, opt, verify},
+    multi::separated_list1,
+    sequence::{pair, preceded},
+    IResult,
+};
+
+use crate::Expression;
+
+use super::parse_numbers::flexible_parse_f64;
+use super::parse_whitespace::spaced;
+
+/// parse the "pick" command, e.g. "pick common:70, uncommon:25, rare:5"
+/// Weights are optional and default to 1, so "pick a, b, c" picks uniformly
+pub fn parse_pick(input: &str) -> IResult<&str, Expression> {
+    let (remain, items) = preceded(
+        pair(tag("pick"), space1),
+        separated_list1(spaced(tag(",")), parse_weighted_item),
+    )(input)?;
+
+    Ok((remain, Expression::Pick(items)))
+}
+
+/// parse a single item with an optional weight, e.g. "common:70" or "common".
+/// Weights too large for an f64 (e.g. "1e400") aren't accepted.
+fn parse_weighted_item(input: &str) -> IResult<&str, (String, f64)> {
+    let (remain, (name, weight)) = pair(
+        parse_item_name,
+        opt(preceded(spaced(tag(":")), verify(flexible_parse_f64, |weight: &f64| weight.is_finite()))),
+    )(input)?;
+
+    Ok((remain, (name, weight.unwrap_or(1.0))))
+}
+
+/// parse the name of an item in a list, e.g. the "rare" in "rare:5".
+/// Names can contain spaces (e.g. "magic sword:1"), so surrounding
+/// whitespace is trimmed rather than treated as a separator.
+/// A ";" ends the list, so that another expression can follow it.
+pub fn parse_item_name(input: &str) -> IResult<&str, String> {
+    map(
+        verify(
+            preceded(space0, take_while1(|c: char| c != ':' && c != ',' && c != ';')),
+            |name: &str| !name.trim().is_empty(),
+        ),
+        |name: &str| name.trim().to_owned(),
+    )(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse::parse_pick::parse_pick, Expression};
+
+    #[test]
+    fn test_pick() {
+        assert_eq!(
+            parse_pick("pick common:70, uncommon:25, rare:5"),
+            Ok((
+                "",
+                Expression::Pick(vec![
+                    ("common".to_owned(), 70.0),
+                    ("uncommon".to_owned(), 25.0),
+                    ("rare".to_owned(), 5.0)
+                ])
+            ))
+        );
+    }
+
+    #[test]
+    fn test_pick_float_weights_and_spaces() {
+        assert_eq!(
+            parse_pick("pick magic sword : 0.5,gold:1e1"),
+            Ok((
+                "",
+                Expression::Pick(vec![
+                    ("magic sword".to_owned(), 0.5),
+                    ("gold".to_owned(), 10.0)
+                ])
+            ))
+        );
+    }
+
+    #[test]
+    fn test_pick_default_weight() {
+        assert_eq!(
+            parse_pick("pick goblin, orc:2"),
+            Ok((
+                "",
+                Expression::Pick(vec![("goblin".to_owned(), 1.0), ("orc".to_owned(), 2.0)])
+            ))
+        );
+        assert!(parse_pick("pick").is_err());
+        assert!(parse_pick("pick ,").is_err());
+        assert_eq!(parse_pick("pick a:1e400, b"), Ok((":1e400, b", Expression::Pick(vec![("a".to_owned(), 1.0)]))));
+    }
+}
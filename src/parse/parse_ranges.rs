@@ -1,77 +1,280 @@
-use nom::{IResult, branch::alt, sequence::separated_pair, bytes::complete::tag};
+use nom::{
+    IResult, branch::alt, sequence::{pair, preceded, separated_pair, tuple}, bytes::complete::tag,
+    character::complete::char, combinator::{map, map_opt, opt, value},
+};
 
-use crate::Expression;
+use crate::{interval::Interval, value::IntValue, Expression};
 
-use super::parse_numbers::{parse_f32, flexible_parse_f32, parse_signed_integer};
+use super::parse_numbers::{
+    parse_f64, parse_f64_before_dots, flexible_parse_f64, parse_signed_integer, with_places,
+};
+use super::parse_variables::parse_int_value;
 use super::parse_whitespace::spaced;
 
 /// parse a float range, e.g. 1.0-5.0
 pub fn parse_float_range(input: &str) -> IResult<&str, Expression> {
-    let (remain, (min , max)) = alt((
+    let (remain, ((min, min_places), (max, max_places))) = alt((
         // handle these cases:
         // <float> - <integer>
         // <float> - <float>
         separated_pair(
-            parse_f32,
+            with_places(parse_f64),
             spaced(tag("-")),
-            flexible_parse_f32
+            with_places(flexible_parse_f64)
         ),
         // handle this case:
         // <integer> - <float>
         separated_pair(
-            parse_signed_integer::<f32>,
+            with_places(parse_signed_integer::<f64>),
             spaced(tag("-")),
-            parse_f32
+            with_places(parse_f64)
         )
     ))
     (input)?;
 
-    Ok((remain, Expression::FloatRange(min, max)))
+    Ok((remain, Expression::FloatRange {
+        min,
+        max,
+        interval: Interval::Closed,
+        decimals: min_places.max(max_places),
+        step: None,
+    }))
 }
 
-/// parse an int range, e.g. 1-5
+/// parse an int range, e.g. 1-5 or 1-maxhp
 pub fn parse_int_range(input: &str) -> IResult<&str, Expression> {
     let (remain, (min , max)) =
         separated_pair(
-            parse_signed_integer::<i64>,
+            parse_int_value,
             spaced(tag("-")),
-            parse_signed_integer::<i64>
+            parse_int_value
         )
         (input)?;
 
-    Ok((remain, Expression::IntRange(min, max)))
+    Ok((remain, Expression::IntRange { min, max, interval: Interval::Closed, step: None }))
 }
 
+/// One end of a range (or its step), which makes it a float range if
+/// it's written with a decimal point or exponent. Floats keep how many
+/// decimal places they were written with.
+enum Bound {
+    Int(IntValue),
+    Float(f64, u32),
+}
+
+impl Bound {
+    fn to_float(&self) -> Option<(f64, u32)> {
+        match self {
+            Bound::Int(IntValue::Literal(value)) => Some((*value as f64, 0)),
+            Bound::Float(value, places) => Some((*value, *places)),
+            // e.g. "0.5..maxhp" isn't allowed, as variables are integers
+            Bound::Int(_) => None,
+        }
+    }
+}
+
+fn parse_bound(input: &str) -> IResult<&str, Bound> {
+    alt((
+        map(with_places(parse_f64), |(value, places)| Bound::Float(value, places)),
+        map(parse_int_value, Bound::Int),
+    ))(input)
+}
+
+/// A float or int range, depending on whether either end (or the step) is a float
+fn range(min: Bound, max: Bound, interval: Interval, step: Option<Bound>) -> Option<Expression> {
+    match (min, max, step) {
+        (Bound::Int(min), Bound::Int(max), None) => Some(Expression::IntRange { min, max, interval, step: None }),
+        (Bound::Int(min), Bound::Int(max), Some(Bound::Int(IntValue::Literal(step)))) => Some(Expression::IntRange {
+            min,
+            max,
+            interval,
+            step: Some(u64::try_from(step).ok().filter(|step| *step > 0)?),
+        }),
+        (min, max, step) => {
+            let (min, min_places) = min.to_float()?;
+            let (max, max_places) = max.to_float()?;
+            let (step, step_places) = match step {
+                Some(step) => {
+                    let (step, places) = step.to_float()?;
+                    if step <= 0.0 {
+                        return None;
+                    }
+                    (Some(step), places)
+                }
+                None => (None, 0),
+            };
+            Some(Expression::FloatRange {
+                min,
+                max,
+                interval,
+                decimals: min_places.max(max_places).max(step_places),
+                step,
+            })
+        }
+    }
+}
+
+/// parse the dots between the ends of a range, and which ends they include
+fn parse_dots(input: &str) -> IResult<&str, Interval> {
+    alt((value(Interval::Closed, tag("..=")), value(Interval::ClosedOpen, tag(".."))))(input)
+}
+
+/// parse a range written with dots, e.g. "0..1", which leaves out
+/// the upper end, or "1..=6", which includes it
+pub fn parse_dotted_range(input: &str) -> IResult<&str, Expression> {
+    map_opt(
+        tuple((
+            alt((
+                map(with_places(parse_f64_before_dots), |(value, places)| Bound::Float(value, places)),
+                map(parse_int_value, Bound::Int),
+            )),
+            spaced(parse_dots),
+            parse_bound,
+        )),
+        |(min, interval, max)| range(min, max, interval, None),
+    )(input)
+}
+
+/// parse a range in interval notation, where "[" and "]" include
+/// an end and "(" and ")" leave it out, e.g. "[0, 1)" or "(0, 10]"
+pub fn parse_bracketed_range(input: &str) -> IResult<&str, Expression> {
+    map_opt(
+        tuple((
+            alt((value(true, char('[')), value(false, char('(')))),
+            spaced(parse_bound),
+            char(','),
+            spaced(parse_bound),
+            alt((value(true, char(']')), value(false, char(')')))),
+        )),
+        |(includes_min, min, _, max, includes_max)| range(min, max, Interval::new(includes_min, includes_max), None),
+    )(input)
+}
+
+/// parse any kind of range, e.g. "1-6", "0.0-1", "0..1" or "[0, 1)"
+pub fn parse_range(input: &str) -> IResult<&str, Expression> {
+    alt((parse_float_range, parse_int_range, parse_dotted_range, parse_bracketed_range))(input)
+}
+
+/// parse a range that only gives every so many numbers, counting
+/// from the lower end, e.g. "0-100 step 5", or any other range
+pub fn parse_stepped_range(input: &str) -> IResult<&str, Expression> {
+    map_opt(
+        pair(parse_range, opt(preceded(spaced(tag("step")), parse_bound))),
+        |(range, step)| match (range, step) {
+            (range, None) => Some(range),
+            (Expression::IntRange { min, max, interval, .. }, Some(step)) => {
+                self::range(Bound::Int(min), Bound::Int(max), interval, Some(step))
+            }
+            (Expression::FloatRange { min, max, interval, decimals, .. }, Some(step)) => self::range(
+                Bound::Float(min, decimals),
+                Bound::Float(max, decimals),
+                interval,
+                Some(step),
+            ),
+            _ => unreachable!(),
+        },
+    )(input)
+}
 
 #[cfg(test)]
 mod tests {
-    use crate::{parse::parse_ranges::{parse_float_range, parse_int_range}, Expression};
+    use crate::{
+        interval::Interval,
+        parse::parse_ranges::{parse_float_range, parse_int_range, parse_range, parse_stepped_range},
+        value::IntValue,
+        Expression,
+    };
+
+    fn int_range(min: IntValue, max: IntValue, interval: Interval) -> Expression {
+        Expression::IntRange { min, max, interval, step: None }
+    }
+
+    fn float_range(min: f64, max: f64, interval: Interval, decimals: u32) -> Expression {
+        Expression::FloatRange { min, max, interval, decimals, step: None }
+    }
 
     #[test]
     fn test_float_range() {
-        assert_eq!(parse_float_range("5.0-10.0"), Ok(("", Expression::FloatRange(5.0, 10.0))));
-        assert_eq!(parse_float_range("5-10.0"), Ok(("", Expression::FloatRange(5.0, 10.0))));
-        assert_eq!(parse_float_range("5.0-10"), Ok(("", Expression::FloatRange(5.0, 10.0))));
+        assert_eq!(parse_float_range("5.0-10.0"), Ok(("", float_range(5.0, 10.0, Interval::Closed, 1))));
+        assert_eq!(parse_float_range("5-10.0"), Ok(("", float_range(5.0, 10.0, Interval::Closed, 1))));
+        assert_eq!(parse_float_range("5.0-10"), Ok(("", float_range(5.0, 10.0, Interval::Closed, 1))));
         assert!(parse_float_range("5-10").is_err());
     }
 
     #[test]
     fn test_float_range_whitespace() {
-        assert_eq!(parse_float_range("5.0 - 10.0"), Ok(("", Expression::FloatRange(5.0, 10.0))));
-        assert_eq!(parse_float_range("5 - 10.0"), Ok(("", Expression::FloatRange(5.0, 10.0))));
-        assert_eq!(parse_float_range("5.0 - 10"), Ok(("", Expression::FloatRange(5.0, 10.0))));
+        assert_eq!(parse_float_range("5.0 - 10.0"), Ok(("", float_range(5.0, 10.0, Interval::Closed, 1))));
+        assert_eq!(parse_float_range("5 - 10.0"), Ok(("", float_range(5.0, 10.0, Interval::Closed, 1))));
+        assert_eq!(parse_float_range("5.0 - 10"), Ok(("", float_range(5.0, 10.0, Interval::Closed, 1))));
         assert!(parse_float_range("5 - 10").is_err());
     }
 
     #[test]
     fn test_int_range() {
-        assert_eq!(parse_int_range("5-10"), Ok(("", Expression::IntRange(5,10))));
+        assert_eq!(parse_int_range("5-10"), Ok(("", int_range(5.into(), 10.into(), Interval::Closed))));
         assert!(parse_int_range("5.0-10").is_err());
     }
 
+    #[test]
+    fn test_int_range_variable() {
+        assert_eq!(
+            parse_int_range("1-maxhp"),
+            Ok(("", int_range(1.into(), IntValue::Variable("maxhp".to_owned()), Interval::Closed)))
+        );
+        assert!(parse_int_range("d20-dex").is_err());
+    }
+
     #[test]
     fn test_int_range_whitespace() {
-        assert_eq!(parse_int_range("5 - 10"), Ok(("", Expression::IntRange(5,10))));
+        assert_eq!(parse_int_range("5 - 10"), Ok(("", int_range(5.into(), 10.into(), Interval::Closed))));
         assert!(parse_int_range("5.0 - 10").is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_half_open_ranges() {
+        assert_eq!(parse_range("0..1"), Ok(("", int_range(0.into(), 1.into(), Interval::ClosedOpen))));
+        assert_eq!(parse_range("1 ..= 6"), Ok(("", int_range(1.into(), 6.into(), Interval::Closed))));
+        assert_eq!(parse_range("0.0..1"), Ok(("", float_range(0.0, 1.0, Interval::ClosedOpen, 1))));
+        assert_eq!(parse_range("-1..0.5"), Ok(("", float_range(-1.0, 0.5, Interval::ClosedOpen, 1))));
+        assert_eq!(parse_range("[0, 1.0)"), Ok(("", float_range(0.0, 1.0, Interval::ClosedOpen, 1))));
+        assert_eq!(parse_range("( 0,1 ]"), Ok(("", int_range(0.into(), 1.into(), Interval::OpenClosed))));
+        assert_eq!(
+            parse_range("(0, maxhp)"),
+            Ok(("", int_range(0.into(), IntValue::Variable("maxhp".to_owned()), Interval::Open)))
+        );
+        assert!(parse_range("0.5..maxhp").is_err());
+        assert!(parse_range("[0, 1").is_err());
+    }
+
+    #[test]
+    fn test_decimals() {
+        assert_eq!(parse_range("1.00-2.00"), Ok(("", float_range(1.0, 2.0, Interval::Closed, 2))));
+        assert_eq!(parse_range("1-2.5e-3"), Ok(("", float_range(1.0, 2.5e-3, Interval::Closed, 4))));
+        assert_eq!(parse_range("[0, 1.000)"), Ok(("", float_range(0.0, 1.0, Interval::ClosedOpen, 3))));
+    }
+
+    #[test]
+    fn test_step() {
+        assert_eq!(
+            parse_stepped_range("0-100 step 5"),
+            Ok(("", Expression::IntRange { min: 0.into(), max: 100.into(), interval: Interval::Closed, step: Some(5) }))
+        );
+        assert_eq!(
+            parse_stepped_range("0..1 step 0.25"),
+            Ok((
+                "",
+                Expression::FloatRange {
+                    min: 0.0,
+                    max: 1.0,
+                    interval: Interval::ClosedOpen,
+                    decimals: 2,
+                    step: Some(0.25)
+                }
+            ))
+        );
+        assert_eq!(parse_stepped_range("1-6 sorted"), Ok((" sorted", int_range(1.into(), 6.into(), Interval::Closed))));
+        assert!(parse_stepped_range("0-100 step 0").is_err());
+        assert!(parse_stepped_range("0-100 step -5").is_err());
+        assert!(parse_stepped_range("0-100 step n").is_err());
+    }
+}
@@ -0,0 +1,79 @@
+use nom::{
+    bytes::complete::tag,
+    character::complete::space1,
+    combinator::opt,
+    sequence::{pair, preceded, tuple},
+    IResult,
+};
+
+use crate::Expression;
+
+use super::parse_numbers::parse_unsigned_integer;
+use super::parse_ranges::parse_int_range;
+use super::parse_whitespace::spaced;
+
+/// parse the "sample" command, e.g. "sample 6 from 1-49",
+/// optionally followed by "sorted"
+pub fn parse_sample(input: &str) -> IResult<&str, Expression> {
+    let (remain, (count, _, range, sorted)) = preceded(
+        pair(tag("sample"), space1),
+        tuple((
+            parse_unsigned_integer::<u64>,
+            spaced(tag("from")),
+            parse_int_range,
+            opt(preceded(space1, tag("sorted"))),
+        )),
+    )(input)?;
+
+    match range {
+        Expression::IntRange { min, max, .. } => Ok((
+            remain,
+            Expression::Sample {
+                count,
+                min,
+                max,
+                sorted: sorted.is_some(),
+            },
+        )),
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse::parse_sample::parse_sample, Expression};
+
+    #[test]
+    fn test_sample() {
+        assert_eq!(
+            parse_sample("sample 6 from 1-49"),
+            Ok((
+                "",
+                Expression::Sample {
+                    count: 6,
+                    min: 1.into(),
+                    max: 49.into(),
+                    sorted: false
+                }
+            ))
+        );
+        assert!(parse_sample("sample 6 1-49").is_err());
+        assert!(parse_sample("sample -6 from 1-49").is_err());
+    }
+
+    #[test]
+    fn test_sample_sorted_huge_range() {
+        assert_eq!(
+            parse_sample("sample 5 from 1 - 1_000_000_000_000 sorted"),
+            Ok((
+                "",
+                Expression::Sample {
+                    count: 5,
+                    min: 1.into(),
+                    max: 1_000_000_000_000.into(),
+                    sorted: true
+                }
+            ))
+        );
+    }
+}
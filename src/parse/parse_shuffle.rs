@@ -0,0 +1,65 @@
+use nom::{
+    bytes::complete::tag,
+    character::complete::space1,
+    multi::separated_list1,
+    sequence::{pair, preceded},
+    IResult,
+};
+
+use crate::Expression;
+
+use super::parse_pick::parse_item_name;
+use super::parse_ranges::parse_int_range;
+use super::parse_whitespace::spaced;
+
+/// parse the "shuffle" command, e.g. "shuffle a, b, c, d"
+pub fn parse_shuffle(input: &str) -> IResult<&str, Expression> {
+    let (remain, items) = preceded(
+        pair(tag("shuffle"), space1),
+        separated_list1(spaced(tag(",")), parse_item_name),
+    )(input)?;
+
+    Ok((remain, Expression::Shuffle(items)))
+}
+
+/// parse the "perm" command, e.g. "perm 1-10"
+pub fn parse_permutation(input: &str) -> IResult<&str, Expression> {
+    let (remain, range) = preceded(pair(tag("perm"), space1), parse_int_range)(input)?;
+
+    match range {
+        Expression::IntRange { min, max, .. } => Ok((remain, Expression::Permutation(min, max))),
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse::parse_shuffle::{parse_permutation, parse_shuffle},
+        Expression,
+    };
+
+    #[test]
+    fn test_shuffle() {
+        assert_eq!(
+            parse_shuffle("shuffle Alice, Bob,Carol , Dan"),
+            Ok((
+                "",
+                Expression::Shuffle(vec![
+                    "Alice".to_owned(),
+                    "Bob".to_owned(),
+                    "Carol".to_owned(),
+                    "Dan".to_owned()
+                ])
+            ))
+        );
+        assert!(parse_shuffle("shuffle").is_err());
+    }
+
+    #[test]
+    fn test_permutation() {
+        assert_eq!(parse_permutation("perm 1-10"), Ok(("", Expression::Permutation(1.into(), 10.into()))));
+        assert_eq!(parse_permutation("perm -2 - 2"), Ok(("", Expression::Permutation((-2).into(), 2.into()))));
+        assert!(parse_permutation("perm 1.0-2.0").is_err());
+    }
+}
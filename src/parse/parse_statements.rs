@@ -0,0 +1,88 @@
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::space0,
+    combinator::{eof, peek, recognize},
+    multi::separated_list1,
+    sequence::{pair, preceded, terminated},
+    IResult,
+};
+
+use crate::Expression;
+
+use super::parse_expression::parse_expression;
+use super::parse_whitespace::spaced;
+
+/// parse what separates expressions on the same line, i.e. ";" or ","
+fn parse_separator(input: &str) -> IResult<&str, &str> {
+    alt((tag(";"), tag(",")))(input)
+}
+
+/// Succeeds (without consuming anything but whitespace) if this is
+/// the end of an expression, i.e. the end of the line or a separator.
+/// For parsers that only match a whole expression, e.g. "+5" as
+/// shorthand for "d20+5"
+pub fn parse_end_of_expression(input: &str) -> IResult<&str, &str> {
+    preceded(space0, peek(alt((eof, recognize(parse_separator)))))(input)
+}
+
+/// parse a line with one or more expressions, separated by
+/// ";" or ",", e.g. "d20+5; 2d6+3" or "coin, 1-10, d20".
+/// The whole line has to be parsed.
+///
+/// Commas in a list of items belong to the list, so
+/// "pick a, b, c" is a single expression.
+pub fn parse_statements(input: &str) -> IResult<&str, Vec<Expression>> {
+    terminated(
+        separated_list1(spaced(parse_separator), preceded(space0, parse_expression)),
+        pair(space0, eof),
+    )(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        interval::Interval,
+        parse::{parse_statements::parse_statements, ParseError},
+        Expression,
+    };
+
+    #[test]
+    fn test_statements() {
+        let (_, statements) = parse_statements("d20+5; 2d6+3").unwrap();
+        assert_eq!(statements.len(), 2);
+
+        let (_, statements) = parse_statements(" coin, 1-10 ,d20 ").unwrap();
+        assert_eq!(statements.len(), 3);
+        assert_eq!(statements[0], Expression::CoinFlip);
+        assert_eq!(statements[1], Expression::IntRange { min: 1.into(), max: 10.into(), interval: Interval::Closed, step: None });
+    }
+
+    #[test]
+    fn test_statements_with_lists() {
+        let (_, statements) = parse_statements("pick a, b; shuffle c, d").unwrap();
+        assert_eq!(
+            statements,
+            [
+                Expression::Pick(vec![("a".to_owned(), 1.0), ("b".to_owned(), 1.0)]),
+                Expression::Shuffle(vec!["c".to_owned(), "d".to_owned()])
+            ]
+        );
+    }
+
+    #[test]
+    fn test_statements_shorthand_and_macros() {
+        let (_, statements) = parse_statements("+5, fireball; +2a").unwrap();
+        assert_eq!(statements.len(), 3);
+        assert!(matches!(statements[1], Expression::CallMacro { .. }));
+    }
+
+    #[test]
+    fn test_trailing_input() {
+        let error = ParseError::new("d20 ?", parse_statements("d20 ?").unwrap_err());
+        assert_eq!(error.message, "can't parse \"?\" at column 5");
+        assert_eq!(error.column, 5);
+        let error = ParseError::new("d20;", parse_statements("d20;").unwrap_err());
+        assert_eq!(error.to_string(), "can't parse \";\" at column 4");
+    }
+}
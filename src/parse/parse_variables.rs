@@ -0,0 +1,99 @@
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{alpha1, alphanumeric1},
+    combinator::{map, recognize, verify},
+    multi::many0,
+    sequence::{pair, separated_pair},
+    IResult,
+};
+
+use crate::{keywords::COMMANDS, value::IntValue, Expression};
+
+use super::parse_dice_roll::parse_dice_atoms;
+use super::parse_numbers::parse_signed_integer;
+use super::parse_whitespace::spaced;
+
+
+/// Tell if a name would be read as a dice roll, e.g. "d20"
+fn looks_like_dice(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next() == Some('d') && chars.next().is_some_and(|c| c.is_ascii_digit())
+}
+
+/// parse the name of a variable, e.g. "str" or "max_hp".
+/// Names start with a letter or underscore, and can't be
+/// a command (e.g. "coin") or look like a dice roll (e.g. "d20")
+pub fn parse_identifier(input: &str) -> IResult<&str, String> {
+    map(
+        verify(
+            recognize(pair(
+                alt((alpha1, tag("_"))),
+                many0(alt((alphanumeric1, tag("_")))),
+            )),
+            |name: &str| !looks_like_dice(name) && !COMMANDS.contains(&name),
+        ),
+        str::to_owned,
+    )(input)
+}
+
+/// parse an integer that is written out or given by a
+/// variable, e.g. the "1" and "maxhp" in "1-maxhp"
+pub fn parse_int_value(input: &str) -> IResult<&str, IntValue> {
+    alt((
+        map(parse_signed_integer::<i64>, IntValue::Literal),
+        map(parse_identifier, IntValue::Variable),
+    ))(input)
+}
+
+/// parse setting a variable, e.g. "str = 3" or "hp = 4d8 + 4"
+pub fn parse_assignment(input: &str) -> IResult<&str, Expression> {
+    let (remain, (name, value)) =
+        separated_pair(parse_identifier, spaced(tag("=")), parse_dice_atoms)(input)?;
+
+    Ok((remain, Expression::Assign { name, value }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dice::atom::DiceExpressionAtom::*,
+        parse::parse_variables::{parse_assignment, parse_identifier},
+        Expression,
+    };
+
+    #[test]
+    fn test_identifier() {
+        assert_eq!(parse_identifier("str"), Ok(("", "str".to_owned())));
+        assert_eq!(parse_identifier("max_hp2 + 1"), Ok((" + 1", "max_hp2".to_owned())));
+        assert_eq!(parse_identifier("dex"), Ok(("", "dex".to_owned())));
+        assert!(parse_identifier("d20").is_err());
+        assert!(parse_identifier("2x").is_err());
+        assert!(parse_identifier("coin").is_err());
+    }
+
+    #[test]
+    fn test_assignment() {
+        assert_eq!(
+            parse_assignment("str = 3"),
+            Ok(("", Expression::Assign { name: "str".to_owned(), value: vec![Constant(3)] }))
+        );
+        assert_eq!(
+            parse_assignment("prof=+2"),
+            Ok(("", Expression::Assign { name: "prof".to_owned(), value: vec![Constant(2)] }))
+        );
+        assert_eq!(
+            parse_assignment("atk = str + prof"),
+            Ok((
+                "",
+                Expression::Assign {
+                    name: "atk".to_owned(),
+                    value: vec![
+                        Variable { name: "str".to_owned(), subtracted: false },
+                        Variable { name: "prof".to_owned(), subtracted: false }
+                    ]
+                }
+            ))
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//! Splitting a line into tokens (e.g. dice, constants, and modifiers)
+//! so that it can be syntax highlighted
+
+use std::ops::Range;
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{alpha1, alphanumeric1, char, one_of, space0, space1},
+    combinator::{map, opt, peek, recognize, value, verify},
+    multi::many0,
+    sequence::{pair, terminated, tuple},
+    IResult,
+};
+
+use crate::keywords::{COMMANDS, DECK_COMMANDS, DECK_PRESETS, DISTRIBUTIONS};
+
+use super::parse_dice_roll::{parse_number_of_dice, parse_roll_modifier};
+use super::parse_numbers::{parse_digit1, parse_float, parse_unsigned_integer};
+use super::parse_ranges::parse_range;
+use super::parse_statements::parse_statements;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenKind {
+    /// A roll without its modifiers, e.g. the "4d6" in "4d6dl1"
+    Dice,
+    /// A number, e.g. the "5" in "d20 + 5"
+    Constant,
+    /// A roll modifier, e.g. the "dl1" in "4d6dl1"
+    Modifier,
+    /// A whole range, e.g. "1-10"
+    Range,
+    /// A command, e.g. "coin" or "draw"
+    Keyword,
+    /// The name of a variable, macro, or list item
+    Identifier,
+    /// An operator or bracket, e.g. "+" or "("
+    Operator,
+    /// What separates expressions on a line, i.e. ";" or ","
+    Separator,
+    Whitespace,
+    /// Input that can't be parsed
+    Invalid,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    /// Where the token is in the line, in bytes
+    pub span: Range<usize>,
+}
+
+/// Split a line into tokens, covering the whole line. Everything
+/// after the point where the line stops parsing is `Invalid`.
+pub fn tokenize(line: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut rest = line;
+    // Modifiers can only come straight after a roll or (in
+    // shorthand like "+5a") a constant, or after another modifier
+    let mut after_roll = false;
+    // Ranges are whole expressions (or follow a command, e.g. "perm 1-10"),
+    // so e.g. the "str-1" in "d20 + str-1" isn't a range
+    let mut range_allowed = true;
+    while !rest.is_empty() {
+        let start = line.len() - rest.len();
+        let (remain, kind) = next_token(rest, after_roll, range_allowed).unwrap_or_else(|_| {
+            // Skip a single character that isn't part of any token
+            let len = rest.chars().next().map_or(1, char::len_utf8);
+            (&rest[len..], TokenKind::Invalid)
+        });
+        after_roll = matches!(kind, TokenKind::Dice | TokenKind::Constant | TokenKind::Modifier);
+        range_allowed = match kind {
+            TokenKind::Whitespace => range_allowed,
+            TokenKind::Separator | TokenKind::Keyword => true,
+            _ => false,
+        };
+        tokens.push(Token {
+            kind,
+            span: start..line.len() - remain.len(),
+        });
+        rest = remain;
+    }
+
+    if let Err(nom::Err::Error(e) | nom::Err::Failure(e)) = parse_statements(line) {
+        mark_invalid_from(&mut tokens, line.len() - e.input.len());
+    }
+    tokens
+}
+
+/// Mark everything from `position` onwards as invalid,
+/// splitting the token that `position` is in, if any
+fn mark_invalid_from(tokens: &mut Vec<Token>, position: usize) {
+    let end = tokens.last().map_or(position, |token| token.span.end);
+    tokens.retain_mut(|token| {
+        if token.span.end > position {
+            token.span.end = position;
+        }
+        token.span.start < token.span.end
+    });
+    if position < end {
+        tokens.push(Token {
+            kind: TokenKind::Invalid,
+            span: position..end,
+        });
+    }
+}
+
+/// parse a word, e.g. a keyword or the name of a variable
+fn parse_word(input: &str) -> IResult<&str, &str> {
+    recognize(pair(alt((alpha1, tag("_"))), many0(alt((alphanumeric1, tag("_"))))))(input)
+}
+
+fn next_token(input: &str, after_roll: bool, range_allowed: bool) -> IResult<&str, TokenKind> {
+    if after_roll {
+        if let Ok(result) = value(TokenKind::Modifier, recognize(parse_roll_modifier))(input) {
+            return Ok(result);
+        }
+    }
+    // The range parsers allow leading whitespace, which should be its own token
+    if range_allowed && !input.starts_with(char::is_whitespace) {
+        if let Ok(result) = value(TokenKind::Range, parse_range)(input) {
+            return Ok(result);
+        }
+    }
+    alt((
+        value(TokenKind::Whitespace, space1),
+        value(TokenKind::Separator, one_of(";,")),
+        value(
+            TokenKind::Dice,
+            tuple((
+                opt(parse_number_of_dice),
+                tag("d"),
+                parse_unsigned_integer::<u8>,
+            )),
+        ),
+        value(TokenKind::Constant, alt((parse_float, parse_digit1))),
+        // A distribution's name is only a keyword before its parameters
+        value(
+            TokenKind::Keyword,
+            terminated(
+                verify(parse_word, |word: &str| DISTRIBUTIONS.contains(&word)),
+                peek(pair(space0, char('('))),
+            ),
+        ),
+        map(
+            parse_word,
+            |word: &str| {
+                if COMMANDS.contains(&word) || DECK_COMMANDS.contains(&word) || DECK_PRESETS.contains(&word) {
+                    TokenKind::Keyword
+                } else {
+                    TokenKind::Identifier
+                }
+            },
+        ),
+        value(TokenKind::Operator, one_of("+-=():")),
+    ))(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{tokenize, Token, TokenKind::*};
+
+    fn kinds(line: &str) -> Vec<(super::TokenKind, &str)> {
+        tokenize(line)
+            .into_iter()
+            .map(|Token { kind, span }| (kind, &line[span]))
+            .collect()
+    }
+
+    #[test]
+    fn test_dice() {
+        assert_eq!(
+            kinds("4d6dl1 + str-1"),
+            [
+                (Dice, "4d6"),
+                (Modifier, "dl1"),
+                (Whitespace, " "),
+                (Operator, "+"),
+                (Whitespace, " "),
+                (Identifier, "str"),
+                (Operator, "-"),
+                (Constant, "1")
+            ]
+        );
+        assert_eq!(kinds("+2a"), [(Operator, "+"), (Constant, "2"), (Modifier, "a")]);
+    }
+
+    #[test]
+    fn test_commands() {
+        assert_eq!(
+            kinds("sample 6 from 1-49; coin"),
+            [
+                (Keyword, "sample"),
+                (Whitespace, " "),
+                (Constant, "6"),
+                (Whitespace, " "),
+                (Keyword, "from"),
+                (Whitespace, " "),
+                (Range, "1-49"),
+                (Separator, ";"),
+                (Whitespace, " "),
+                (Keyword, "coin")
+            ]
+        );
+        assert_eq!(kinds("[0, 1.5); 0..=9"), [(Range, "[0, 1.5)"), (Separator, ";"), (Whitespace, " "), (Range, "0..=9")]);
+        assert_eq!(
+            kinds("exp(0.5); exp"),
+            [
+                (Keyword, "exp"),
+                (Operator, "("),
+                (Constant, "0.5"),
+                (Operator, ")"),
+                (Separator, ";"),
+                (Whitespace, " "),
+                (Identifier, "exp")
+            ]
+        );
+        assert_eq!(kinds("0-100 step 5"), [(Range, "0-100"), (Whitespace, " "), (Keyword, "step"), (Whitespace, " "), (Constant, "5")]);
+    }
+
+    #[test]
+    fn test_invalid_trailing_input() {
+        assert_eq!(
+            kinds("d20 ? x"),
+            [(Dice, "d20"), (Whitespace, " "), (Invalid, "? x")]
+        );
+        assert_eq!(kinds("1d6 +"), [(Dice, "1d6"), (Whitespace, " "), (Invalid, "+")]);
+    }
+}
@@ -0,0 +1,122 @@
+use std::sync::{Arc, Mutex};
+
+use rand_api::{
+    help::{Topic, HELP},
+    Session,
+};
+use reedline::{Completer, Span, Suggestion};
+
+/// Completes commands, roll modifiers, deck presets,
+/// and the names of the session's variables and macros
+pub struct ReplCompleter {
+    session: Arc<Mutex<Session>>,
+}
+
+impl ReplCompleter {
+    pub fn new(session: Arc<Mutex<Session>>) -> Self {
+        ReplCompleter { session }
+    }
+
+    /// The words that can be completed, with a description of each.
+    /// Keywords come from the help table and are described by their syntax.
+    fn candidates(&self, after_dice: bool) -> Vec<(String, &'static str)> {
+        // Right after a roll (e.g. "d20a") only a modifier makes sense
+        let keywords = HELP
+            .iter()
+            .filter(|entry| (entry.topic == Topic::Modifiers) == after_dice)
+            .flat_map(|entry| entry.completions.iter().map(|word| (word.to_string(), entry.syntax)));
+        let mut candidates: Vec<_> = keywords.collect();
+        if after_dice {
+            return candidates;
+        }
+
+        let session = self.session.lock().unwrap();
+        candidates.extend(session.macros().map(|definition| (definition.name.clone(), "macro")));
+        candidates.extend(session.variables().keys().map(|name| (name.clone(), "variable")));
+        candidates
+    }
+}
+
+/// Find the start of the partly typed word that ends at `pos`, and
+/// whether it's a modifier on a dice roll, e.g. the "ad" in "2d20ad"
+fn partial_word(line: &str, pos: usize) -> (usize, bool) {
+    let before = &line[..pos];
+    let word_start = before
+        .char_indices()
+        .rev()
+        .find(|&(_, c)| !(c.is_alphanumeric() || c == '_'))
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    let word = &before[word_start..];
+    // A word that starts with a number or a die (e.g. "3d6" or "d20")
+    // is a roll, so only the letters after its last digit are completed
+    let mut chars = word.chars();
+    let is_roll = match chars.next() {
+        Some(c) if c.is_ascii_digit() => true,
+        Some('d') => chars.next().is_some_and(|c| c.is_ascii_digit()),
+        _ => false,
+    };
+    if is_roll {
+        let modifier_start = word.rfind(|c: char| c.is_ascii_digit()).map_or(0, |i| i + 1);
+        (word_start + modifier_start, true)
+    } else {
+        (word_start, false)
+    }
+}
+
+impl Completer for ReplCompleter {
+    fn complete(&mut self, line: &str, pos: usize) -> Vec<Suggestion> {
+        let (start, after_dice) = partial_word(line, pos);
+        let partial = &line[start..pos];
+        let mut suggestions: Vec<Suggestion> = self
+            .candidates(after_dice)
+            .into_iter()
+            .filter(|(word, _)| word.starts_with(partial) && word != partial)
+            .map(|(word, description)| Suggestion {
+                value: word,
+                description: Some(description.to_owned()),
+                extra: None,
+                span: Span::new(start, pos),
+                append_whitespace: !after_dice,
+            })
+            .collect();
+        suggestions.sort_by(|a, b| a.value.cmp(&b.value));
+        suggestions.dedup_by(|a, b| a.value == b.value);
+        suggestions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::partial_word;
+
+    fn partial(line: &str) -> (&str, bool) {
+        let (start, after_dice) = partial_word(line, line.len());
+        (&line[start..], after_dice)
+    }
+
+    #[test]
+    fn words() {
+        assert_eq!(partial(""), ("", false));
+        assert_eq!(partial("sh"), ("sh", false));
+        assert_eq!(partial("3d6 + stre"), ("stre", false));
+        assert_eq!(partial("x = dr"), ("dr", false));
+        assert_eq!(partial("pick café"), ("café", false));
+        assert_eq!(partial_word("shuffle a, b", 3), (0, false));
+    }
+
+    #[test]
+    fn modifiers_after_dice() {
+        assert_eq!(partial("2d20ad"), ("ad", true));
+        assert_eq!(partial("d20"), ("", true));
+        assert_eq!(partial("4d6d"), ("d", true));
+        assert_eq!(partial("d20 + 2d8r"), ("r", true));
+    }
+
+    #[test]
+    fn non_ascii() {
+        assert_eq!(partial("2×"), ("", false));
+        assert_eq!(partial("pick 10♥"), ("", false));
+        assert_eq!(partial("a—b"), ("b", false));
+        assert_eq!(partial("♥d20a"), ("a", true));
+    }
+}
@@ -0,0 +1,32 @@
+use nu_ansi_term::{Color, Style};
+use rand_api::{tokenize, TokenKind};
+use reedline::{Highlighter, StyledText};
+
+/// Colours each part of an expression as it's typed,
+/// e.g. dice, constants, and roll modifiers
+pub struct ReplHighlighter;
+
+fn token_style(kind: TokenKind) -> Style {
+    match kind {
+        TokenKind::Dice => Style::new().bold().fg(Color::Cyan),
+        TokenKind::Constant => Style::new().fg(Color::Yellow),
+        TokenKind::Modifier => Style::new().fg(Color::Magenta),
+        TokenKind::Range => Style::new().fg(Color::Green),
+        TokenKind::Keyword => Style::new().bold().fg(Color::Blue),
+        TokenKind::Identifier => Style::new().fg(Color::LightCyan),
+        TokenKind::Operator | TokenKind::Separator | TokenKind::Whitespace => Style::new(),
+        TokenKind::Invalid => Style::new().underline().fg(Color::Red),
+    }
+}
+
+impl Highlighter for ReplHighlighter {
+    fn highlight(&self, line: &str, _cursor: usize) -> StyledText {
+        let mut styled_text = StyledText::new();
+        // A line carried over by the validator is parsed as one line.
+        // Both characters are one byte, so the spans still line up.
+        for token in tokenize(&line.replace('\n', " ")) {
+            styled_text.push((token_style(token.kind), line[token.span].to_owned()));
+        }
+        styled_text
+    }
+}
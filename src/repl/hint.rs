@@ -0,0 +1,73 @@
+use std::sync::{Arc, Mutex};
+
+use nu_ansi_term::{Color, Style};
+use rand_api::{normalize, validate, Session};
+use reedline::{Hinter, History};
+
+/// Shows after the cursor whether the line parses, and if it does, what
+/// it could evaluate to, e.g. "3d6 → 3..18, avg 10.5". It shares the
+/// REPL's session so it knows the values of variables.
+pub struct ReplHinter {
+    session: Arc<Mutex<Session>>,
+}
+
+impl ReplHinter {
+    pub fn new(session: Arc<Mutex<Session>>) -> Self {
+        ReplHinter { session }
+    }
+
+    fn hint(&self, line: &str) -> Option<(Style, String)> {
+        if line.trim().is_empty() || super::is_repl_command(line) {
+            return None;
+        }
+        // Lines carried over by the validator are evaluated as one line
+        let line = line.replace('\n', " ");
+        match validate(&line) {
+            Err(e) => Some((Style::new().fg(Color::Red), format!("  ✗ {e}"))),
+            Ok(()) => {
+                let description = self.session.lock().unwrap().describe(&line);
+                // The line as it was understood is shown if it was written
+                // differently, so that e.g. "2d" is seen to be "1d20d + 2"
+                let normalized = normalize(&line)
+                    .ok()
+                    .filter(|normalized| !same_ignoring_spaces(normalized, &line));
+                let hint = match (normalized, description) {
+                    (Some(normalized), Some(description)) => format!("  = {normalized} → {description}"),
+                    (Some(normalized), None) => format!("  = {normalized}"),
+                    (None, Some(description)) => format!("  → {description}"),
+                    (None, None) => "  ✓".to_owned(),
+                };
+                Some((Style::new().fg(Color::DarkGray), hint))
+            }
+        }
+    }
+}
+
+fn same_ignoring_spaces(a: &str, b: &str) -> bool {
+    a.chars().filter(|c| !c.is_whitespace()).eq(b.chars().filter(|c| !c.is_whitespace()))
+}
+
+impl Hinter for ReplHinter {
+    fn handle(&mut self, line: &str, pos: usize, _history: &dyn History, use_ansi_coloring: bool) -> String {
+        // Only hint at the end of the line, so the hint doesn't get in
+        // the way of what's being typed
+        if pos < line.len() {
+            return String::new();
+        }
+        match self.hint(line) {
+            Some((style, hint)) if use_ansi_coloring => style.paint(hint).to_string(),
+            Some((_style, hint)) => hint,
+            None => String::new(),
+        }
+    }
+
+    // The hint describes the line rather than continuing it,
+    // so there's nothing to accept into the buffer
+    fn complete_hint(&self) -> String {
+        String::new()
+    }
+
+    fn next_hint_token(&self) -> String {
+        String::new()
+    }
+}
@@ -0,0 +1,59 @@
+use reedline::{History, HistoryItem, HistoryItemId, HistorySessionId, Result, SearchQuery};
+
+use super::can_run;
+
+/// History that leaves out lines that don't parse. Reedline saves a line
+/// as soon as it's submitted, but those lines are put back to be fixed
+/// rather than run, so only the fixed line should be saved.
+pub struct ReplHistory<H> {
+    history: H,
+}
+
+impl<H: History> ReplHistory<H> {
+    pub fn new(history: H) -> Self {
+        ReplHistory { history }
+    }
+}
+
+impl<H: History> History for ReplHistory<H> {
+    fn save(&mut self, h: HistoryItem) -> Result<HistoryItem> {
+        // Lines carried over by the validator are evaluated as one line
+        if can_run(&h.command_line.replace('\n', " ")) {
+            self.history.save(h)
+        } else {
+            Ok(h)
+        }
+    }
+
+    fn load(&self, id: HistoryItemId) -> Result<HistoryItem> {
+        self.history.load(id)
+    }
+
+    fn count(&self, query: SearchQuery) -> Result<i64> {
+        self.history.count(query)
+    }
+
+    fn search(&self, query: SearchQuery) -> Result<Vec<HistoryItem>> {
+        self.history.search(query)
+    }
+
+    fn update(&mut self, id: HistoryItemId, updater: &dyn Fn(HistoryItem) -> HistoryItem) -> Result<()> {
+        self.history.update(id, updater)
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.history.clear()
+    }
+
+    fn delete(&mut self, h: HistoryItemId) -> Result<()> {
+        self.history.delete(h)
+    }
+
+    fn sync(&mut self) -> std::io::Result<()> {
+        self.history.sync()
+    }
+
+    fn session(&self) -> Option<HistorySessionId> {
+        self.history.session()
+    }
+}
@@ -0,0 +1,28 @@
+//! Parts of the interactive REPL that plug into reedline
+
+pub mod completion;
+pub mod highlight;
+pub mod hint;
+pub mod history;
+pub mod render;
+pub mod validate;
+
+use rand_api::{
+    help::{Topic, HELP},
+    validate,
+};
+
+/// Whether a line is one of the commands the REPL handles itself (e.g.
+/// "help dice"), rather than an expression for the session to evaluate
+pub fn is_repl_command(line: &str) -> bool {
+    let first_word = line.split_whitespace().next().unwrap_or_default();
+    HELP.iter()
+        .filter(|entry| entry.topic == Topic::Repl)
+        .any(|entry| entry.names.contains(&first_word))
+}
+
+/// Whether a line can be run, i.e. it parses or it's a REPL command.
+/// Lines that can't are put back to be fixed, and aren't saved in the history.
+pub fn can_run(line: &str) -> bool {
+    validate(line).is_ok() || is_repl_command(line)
+}
@@ -0,0 +1,59 @@
+use nu_ansi_term::{Color, Style};
+
+use rand_api::{DieRoll, Evaluation, Outcome};
+
+/// Colours the output of a line before it's printed, e.g. natural 20s in
+/// green, natural 1s in red, dropped dice dimmed and struck through, and
+/// errors in red. Without colour the output is printed as it is.
+pub struct Renderer {
+    pub color: bool,
+}
+
+impl Renderer {
+    /// Each outcome on its own line, the same as the evaluation's text
+    pub fn render(&self, evaluation: &Evaluation) -> String {
+        let outcomes: Vec<String> = evaluation.outcomes.iter().map(|outcome| self.render_outcome(outcome)).collect();
+        outcomes.join("\n")
+    }
+
+    /// An error that isn't part of an evaluation, e.g. for a line that doesn't parse
+    pub fn render_error(&self, message: &str) -> String {
+        self.paint_error(&format!("Error: {message}"))
+    }
+
+    /// A single outcome, e.g. so that errors can be printed apart from the rest
+    pub fn render_outcome(&self, outcome: &Outcome) -> String {
+        if !self.color {
+            return outcome.to_string();
+        }
+        match outcome {
+            Outcome::Error { .. } => self.paint_error(&outcome.to_string()),
+            Outcome::Macro { outcomes, .. } => {
+                let outcomes: Vec<String> = outcomes.iter().map(|outcome| self.render_outcome(outcome)).collect();
+                outcomes.join("\n")
+            }
+            _ => outcome.to_string_with(&render_die),
+        }
+    }
+
+    fn paint_error(&self, error: &str) -> String {
+        if self.color {
+            Style::new().fg(Color::Red).paint(error).to_string()
+        } else {
+            error.to_owned()
+        }
+    }
+}
+
+fn render_die(die: &DieRoll, sides: u8) -> String {
+    if !die.kept {
+        return Style::new().dimmed().strikethrough().paint(die.value.to_string()).to_string();
+    }
+    // Natural 20s and 1s are what matter on a d20
+    let style = match (sides, die.value) {
+        (20, 20) => Style::new().bold().fg(Color::Green),
+        (20, 1) => Style::new().bold().fg(Color::Red),
+        _ => Style::new(),
+    };
+    style.paint(die.value.to_string()).to_string()
+}
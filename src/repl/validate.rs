@@ -0,0 +1,19 @@
+use reedline::{ValidationResult, Validator};
+
+/// Lets a line carry on over several lines while a parenthesis is left
+/// open, e.g. "(str +" followed by "dex)d6". Lines that are complete but
+/// don't parse are dealt with when they're submitted, so that Enter
+/// doesn't add a line break to them.
+pub struct ReplValidator;
+
+impl Validator for ReplValidator {
+    fn validate(&self, line: &str) -> ValidationResult {
+        let open = line.matches('(').count();
+        let closed = line.matches(')').count();
+        if open > closed {
+            ValidationResult::Incomplete
+        } else {
+            ValidationResult::Complete
+        }
+    }
+}
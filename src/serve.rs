@@ -0,0 +1,459 @@
+//! A small HTTP server for evaluating expressions, e.g.
+//! `GET /roll?expr=3d6` or `POST /eval` with the expressions as the body.
+//! Each request gets its own session, with the saved macros.
+
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use rand_api::{Evaluation, Macro, Outcome, Session};
+
+/// The most bytes the request line and headers can take up
+const MAX_HEAD_LEN: u64 = 8 * 1024;
+
+/// The most bytes a request's body can be
+const MAX_BODY_LEN: usize = 16 * 1024;
+
+/// The most lines a single "POST /eval" can evaluate
+const MAX_LINES: usize = 100;
+
+/// The most values (dice, numbers, cards, etc.) a single request can
+/// output, so that e.g. macros that call each other many times over
+/// can't keep a connection's thread busy for what is practically forever
+const MAX_OUTPUT_VALUES: usize = 100_000;
+
+/// The most bytes a response's body can be
+const MAX_RESPONSE_LEN: usize = 1024 * 1024;
+
+/// How long a client has to send its whole request
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The most connections handled at once, each on its own thread.
+/// Any more are turned away until one of them is done.
+const MAX_CONNECTIONS: usize = 64;
+
+#[derive(Debug, PartialEq)]
+struct Request {
+    method: String,
+    path: String,
+    query: String,
+    body: String,
+}
+
+#[derive(Debug, PartialEq)]
+struct Response {
+    status: u16,
+    body: String,
+}
+
+impl Response {
+    fn error(status: u16, message: &str) -> Self {
+        // The messages are all written here, so they don't need escaping
+        Response {
+            status,
+            body: format!(r#"{{"error":"{message}"}}"#),
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            408 => "Request Timeout",
+            413 => "Payload Too Large",
+            422 => "Unprocessable Entity",
+            431 => "Request Header Fields Too Large",
+            503 => "Service Unavailable",
+            _ => "Internal Server Error",
+        }
+    }
+
+    fn write_to(&self, stream: &mut impl Write) -> io::Result<()> {
+        write!(
+            stream,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            self.reason(),
+            self.body.len(),
+            self.body
+        )?;
+        stream.flush()
+    }
+}
+
+/// Serve requests on `address` (e.g. "127.0.0.1:8080") until the process is stopped
+pub fn serve(address: &str, macros: Vec<Macro>) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    eprintln!("Listening on http://{}", listener.local_addr()?);
+    let open_connections = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let mut stream = match stream.and_then(|stream| {
+            stream.set_read_timeout(Some(TIMEOUT))?;
+            stream.set_write_timeout(Some(TIMEOUT))?;
+            Ok(stream)
+        }) {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("Couldn't accept a connection: {e}");
+                continue;
+            }
+        };
+        let Some(slot) = ConnectionSlot::take(&open_connections) else {
+            // The response is small enough that writing it won't wait on the client
+            if let Err(e) = Response::error(503, "too many connections, try again later").write_to(&mut stream) {
+                eprintln!("Couldn't respond to a request: {e}");
+            }
+            continue;
+        };
+        let macros = macros.clone();
+        thread::spawn(move || {
+            if let Err(e) = handle_connection(stream, &macros) {
+                eprintln!("Couldn't respond to a request: {e}");
+            }
+            drop(slot);
+        });
+    }
+    Ok(())
+}
+
+/// One of the `MAX_CONNECTIONS` connections that can be handled
+/// at once, which is given back when it's dropped
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    fn take(open_connections: &Arc<AtomicUsize>) -> Option<Self> {
+        open_connections
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |open| (open < MAX_CONNECTIONS).then_some(open + 1))
+            .ok()?;
+        Some(ConnectionSlot(Arc::clone(open_connections)))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Reads from a stream until a deadline, so that a client sending a
+/// byte at a time can't keep its connection open for longer than that
+struct DeadlineReader<'a> {
+    stream: &'a TcpStream,
+    deadline: Instant,
+}
+
+impl<'a> DeadlineReader<'a> {
+    fn new(stream: &'a TcpStream, timeout: Duration) -> Self {
+        DeadlineReader { stream, deadline: Instant::now() + timeout }
+    }
+}
+
+impl Read for DeadlineReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        self.stream.set_read_timeout(Some(remaining))?;
+        let mut stream = self.stream;
+        stream.read(buf)
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, macros: &[Macro]) -> io::Result<()> {
+    let response = match read_request(&mut BufReader::new(DeadlineReader::new(&stream, TIMEOUT))) {
+        Ok(request) => respond(&request, macros),
+        Err(response) => response,
+    };
+    response.write_to(&mut stream)
+}
+
+fn timed_out(e: &io::Error) -> bool {
+    matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut)
+}
+
+fn read_request(reader: &mut impl BufRead) -> Result<Request, Response> {
+    let mut head = reader.take(MAX_HEAD_LEN);
+    let mut request_line = String::new();
+    read_head_line(&mut head, &mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(Response::error(400, "malformed request line"));
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        read_head_line(&mut head, &mut header)?;
+        if header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| Response::error(400, "invalid Content-Length"))?;
+            }
+        }
+    }
+    if content_length > MAX_BODY_LEN {
+        return Err(Response::error(413, "the body is too large"));
+    }
+
+    let mut body = vec![0; content_length];
+    head.into_inner().read_exact(&mut body).map_err(|e| {
+        if timed_out(&e) {
+            Response::error(408, "the request took too long")
+        } else {
+            Response::error(400, "the body is shorter than its Content-Length")
+        }
+    })?;
+    let body = String::from_utf8(body).map_err(|_| Response::error(400, "the body isn't UTF-8"))?;
+    Ok(Request {
+        method: method.to_owned(),
+        path: path.to_owned(),
+        query: query.to_owned(),
+        body,
+    })
+}
+
+/// Read a line of the request's head, which has to fit in `MAX_HEAD_LEN`
+fn read_head_line(head: &mut impl BufRead, line: &mut String) -> Result<(), Response> {
+    match head.read_line(line) {
+        Ok(_) if line.ends_with('\n') => Ok(()),
+        // The limit was hit before the end of the line
+        Ok(_) if !line.is_empty() => Err(Response::error(431, "the request's headers are too large")),
+        Ok(_) => Err(Response::error(400, "the request ended early")),
+        Err(e) if timed_out(&e) => Err(Response::error(408, "the request took too long")),
+        Err(_) => Err(Response::error(400, "the request isn't valid UTF-8")),
+    }
+}
+
+fn respond(request: &Request, macros: &[Macro]) -> Response {
+    let mut session = Session::new();
+    session.set_output_limit(Some(MAX_OUTPUT_VALUES));
+    macros.iter().cloned().for_each(|definition| session.add_macro(definition));
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/roll") => {
+            let expression = request
+                .query
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("expr="))
+                .map(percent_decode);
+            match expression {
+                Some(Some(expression)) if !expression.trim().is_empty() => {
+                    let evaluation = session.evaluate(&expression);
+                    let body = evaluation.to_json();
+                    if let Some(error) = output_error(&session, body.len()) {
+                        return error;
+                    }
+                    Response {
+                        status: status(&evaluation),
+                        body,
+                    }
+                }
+                Some(None) => Response::error(400, "the expr parameter isn't valid percent-encoded UTF-8"),
+                _ => Response::error(400, "missing the expr parameter, e.g. /roll?expr=3d6"),
+            }
+        }
+        // Each line of the body is evaluated in the same session,
+        // so e.g. a variable set on one line can be used on the next
+        ("POST", "/eval") => {
+            let lines: Vec<&str> = request.body.lines().filter(|line| !line.trim().is_empty()).collect();
+            if lines.is_empty() {
+                return Response::error(400, "the body should have an expression on each line");
+            }
+            if lines.len() > MAX_LINES {
+                return Response::error(413, "too many lines to evaluate");
+            }
+            let mut worst_status = 200;
+            let mut json: Vec<String> = vec![];
+            let mut response_len = "[]".len();
+            for line in lines {
+                let evaluation = session.evaluate(line);
+                let line_json = evaluation.to_json();
+                // Each line after the first is preceded by a comma
+                response_len += line_json.len() + 1;
+                if let Some(error) = output_error(&session, response_len) {
+                    return error;
+                }
+                worst_status = worst_status.max(status(&evaluation));
+                json.push(line_json);
+            }
+            Response {
+                status: worst_status,
+                body: format!("[{}]", json.join(",")),
+            }
+        }
+        (_, "/roll") | (_, "/eval") => Response::error(405, "use GET /roll or POST /eval"),
+        _ => Response::error(404, "not found (try GET /roll?expr=3d6 or POST /eval)"),
+    }
+}
+
+/// An error if the request has output more values than it can, or
+/// if its response (`response_len` bytes so far) would be too large
+fn output_error(session: &Session, response_len: usize) -> Option<Response> {
+    if session.output_limit_reached() {
+        Some(Response::error(422, &format!("a request can't output more than {MAX_OUTPUT_VALUES} values")))
+    } else if response_len > MAX_RESPONSE_LEN {
+        Some(Response::error(413, "the response would be too large"))
+    } else {
+        None
+    }
+}
+
+/// 400 if the line couldn't be parsed, 422 if it parsed but
+/// couldn't be evaluated (e.g. an unknown variable), otherwise 200
+fn status(evaluation: &Evaluation) -> u16 {
+    let parse_error = |outcome: &Outcome| matches!(outcome, Outcome::Error { column: Some(_), .. });
+    if evaluation.outcomes.iter().any(parse_error) {
+        400
+    } else if !evaluation.is_ok() {
+        422
+    } else {
+        200
+    }
+}
+
+/// Decode a query string value, e.g. "3d6%2B2" to "3d6+2".
+/// A "+" is a space, as in HTML forms.
+fn percent_decode(value: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+    while let Some((&byte, after)) = rest.split_first() {
+        rest = after;
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = rest.get(..2)?;
+                bytes.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+                rest = &rest[2..];
+            }
+            _ => bytes.push(byte),
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufReader, Write},
+        net::{TcpListener, TcpStream},
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant},
+    };
+
+    use super::{percent_decode, read_request, respond, ConnectionSlot, DeadlineReader, Request, MAX_CONNECTIONS};
+
+    fn request(text: &str) -> Request {
+        read_request(&mut text.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn decode() {
+        assert_eq!(percent_decode("3d6%2B2"), Some("3d6+2".to_owned()));
+        assert_eq!(percent_decode("d20+adv"), Some("d20 adv".to_owned()));
+        assert_eq!(percent_decode("%E2%99%A5"), Some("♥".to_owned()));
+        assert_eq!(percent_decode("%2"), None);
+        assert_eq!(percent_decode("%ff"), None);
+    }
+
+    #[test]
+    fn routes() {
+        let roll = respond(&request("GET /roll?expr=3d6%2B2 HTTP/1.1\r\nHost: x\r\n\r\n"), &[]);
+        assert_eq!(roll.status, 200);
+        assert!(roll.body.starts_with(r#"{"input":"3d6+2","ok":true"#));
+
+        let parse_error = respond(&request("GET /roll?expr=3d6+%3F HTTP/1.1\r\n\r\n"), &[]);
+        assert_eq!(parse_error.status, 400);
+        assert!(parse_error.body.contains(r#""column":5"#));
+
+        let eval = respond(&post("x = 3\n(x)d6\ny\n"), &[]);
+        assert_eq!(eval.status, 422);
+        assert!(eval.body.contains(r#""number_of_dice":3"#));
+
+        assert_eq!(respond(&request("GET /nope HTTP/1.1\r\n\r\n"), &[]).status, 404);
+        assert_eq!(respond(&request("POST /roll HTTP/1.1\r\n\r\n"), &[]).status, 405);
+    }
+
+    #[test]
+    fn limits() {
+        let too_long = format!("POST /eval HTTP/1.1\r\nContent-Length: {}\r\n\r\n", 1 << 20);
+        assert_eq!(read_request(&mut too_long.as_bytes()).unwrap_err().status, 413);
+        let huge_header = format!("GET /roll HTTP/1.1\r\nX: {}\r\n\r\n", "a".repeat(10_000));
+        assert_eq!(read_request(&mut huge_header.as_bytes()).unwrap_err().status, 431);
+    }
+
+    fn post(body: &str) -> Request {
+        request(&format!("POST /eval HTTP/1.1\r\nContent-Length: {}\r\n\r\n{body}", body.len()))
+    }
+
+    #[test]
+    fn output_limits() {
+        // About 70 MB of numbers
+        let perms = "perm 1-99999\n".repeat(100);
+        assert_eq!(respond(&post(&perms), &[]).status, 422);
+
+        // Each macro calls the one before it ten times
+        let mut macros = "def m0 = 1d1\n".to_owned();
+        for level in 1..10 {
+            macros.push_str(&format!("def m{level} = {}\n", vec![format!("m{}", level - 1); 10].join("; ")));
+        }
+        macros.push_str(&"m9; m9; m9; m9\n".repeat(20));
+        assert_eq!(respond(&post(&macros), &[]).status, 422);
+
+        // Fewer values than the limit, but each die takes up a few bytes
+        let dice = "999d6\n".repeat(99);
+        assert_eq!(respond(&post(&dice), &[]).status, 413);
+    }
+
+    #[test]
+    fn slow_requests_time_out() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        let start = Instant::now();
+        // The request is never finished, but keeps trickling in until the test is done,
+        // so only the deadline can stop it from being read
+        client.write_all(b"GET /roll?expr=3d6 HTTP/1.1\r\n").unwrap();
+        let done = Arc::new(AtomicBool::new(false));
+        let trickle = std::thread::spawn({
+            let done = Arc::clone(&done);
+            move || {
+                while !done.load(Ordering::SeqCst) && client.write_all(b"X").is_ok() {
+                    std::thread::sleep(Duration::from_millis(20));
+                }
+            }
+        });
+        let reader = DeadlineReader::new(&server, Duration::from_millis(200));
+        assert_eq!(read_request(&mut BufReader::new(reader)).unwrap_err().status, 408);
+        // Far longer than the deadline, so that a slow machine doesn't fail the test
+        assert!(start.elapsed() < Duration::from_secs(5));
+        done.store(true, Ordering::SeqCst);
+        trickle.join().unwrap();
+    }
+
+    #[test]
+    fn connection_limit() {
+        let open = Arc::new(AtomicUsize::new(0));
+        let slots: Vec<ConnectionSlot> = (0..MAX_CONNECTIONS).map(|_| ConnectionSlot::take(&open).unwrap()).collect();
+        assert!(ConnectionSlot::take(&open).is_none());
+        drop(slots);
+        assert!(ConnectionSlot::take(&open).is_some());
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::{BTreeMap, HashMap};
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{
+    cards::deck::Deck,
+    describe::describe_expression,
+    evaluate::evaluate_expression,
+    macros::Macro,
+    outcome::{Evaluation, Outcome},
+    parse::{parse_statements, ParseError},
+    Expression,
+};
+
+/// Settings that change how a session behaves
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Settings {
+    /// How many evaluated lines are kept in the session's history.
+    /// The oldest lines are forgotten first.
+    pub history_limit: usize,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings { history_limit: 1000 }
+    }
+}
+
+/// A line that was evaluated in a session, and what it evaluated to
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HistoryEntry {
+    pub input: String,
+    pub output: String,
+}
+
+/// The state that is kept between evaluations, e.g. the random
+/// number generator, variables, macros, and the current deck of cards.
+/// Use `evaluate` instead for one-off evaluations.
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub(crate) rng: StdRng,
+    pub(crate) deck: Deck,
+    pub(crate) variables: HashMap<String, i64>,
+    pub(crate) macros: BTreeMap<String, Macro>,
+    /// How many macros are currently being evaluated inside
+    /// each other, so that recursive macros can be stopped
+    pub(crate) macro_depth: usize,
+    /// How many macros have been called while evaluating the current
+    /// line, so that macros that each call several others can be stopped
+    pub(crate) macro_calls: usize,
+    /// The most values (dice, numbers, cards, etc.) the session
+    /// can output, or `None` if there's no limit
+    pub(crate) output_limit: Option<usize>,
+    /// How many values the session has output since the limit was set
+    pub(crate) output_len: usize,
+    history: Vec<HistoryEntry>,
+    settings: Settings,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::with_rng(StdRng::from_entropy())
+    }
+
+    /// A session whose rolls, shuffles, draws, etc. are the
+    /// same every time it is created with the same seed
+    pub fn seeded(seed: u64) -> Self {
+        Self::with_rng(StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(rng: StdRng) -> Self {
+        Session {
+            rng,
+            deck: Deck::default(),
+            variables: HashMap::new(),
+            macros: BTreeMap::new(),
+            macro_depth: 0,
+            macro_calls: 0,
+            output_limit: None,
+            output_len: 0,
+            history: vec![],
+            settings: Settings::default(),
+        }
+    }
+
+    /// Evaluate a line with one or more expressions, e.g. "3d6" or
+    /// "d20+5; 2d6+3", and add it to the session's history.
+    /// Each expression's result is on its own line.
+    pub fn eval(&mut self, expression: &str) -> String {
+        self.evaluate(expression).to_string()
+    }
+
+    /// Like `eval`, but gives what each expression evaluated to
+    /// (e.g. each die rolled) rather than text.
+    /// A line that doesn't parse gives a single error.
+    pub fn evaluate(&mut self, line: &str) -> Evaluation {
+        self.macro_calls = 0;
+        let outcomes = match parse_statements(line) {
+            Ok((_remainder, expressions)) => expressions
+                .into_iter()
+                .map(|parsed| evaluate_expression(parsed, self))
+                .collect(),
+            Err(e) => {
+                let error = ParseError::new(line, e);
+                vec![Outcome::Error { message: error.message, column: Some(error.column) }]
+            }
+        };
+        let evaluation = Evaluation { input: line.to_owned(), outcomes };
+        self.record(line, &evaluation.to_string());
+        evaluation
+    }
+
+    /// Evaluate an expression that was parsed already (e.g. with `parse`)
+    /// or built in code. It isn't added to the session's history.
+    pub fn evaluate_expression(&mut self, expression: Expression) -> Outcome {
+        self.macro_calls = 0;
+        evaluate_expression(expression, self)
+    }
+
+    /// Describe what a line could evaluate to without evaluating it,
+    /// e.g. "3..18, avg 10.5" for "3d6". Expressions that can't be
+    /// described (e.g. "shuffle a, b") are left out, and `None` is
+    /// returned if none of them can be, or if the line doesn't parse.
+    pub fn describe(&self, line: &str) -> Option<String> {
+        let (_remainder, expressions) = parse_statements(line).ok()?;
+        let descriptions: Vec<String> = expressions
+            .iter()
+            .filter_map(|expression| describe_expression(expression, self))
+            .collect();
+        if descriptions.is_empty() {
+            None
+        } else {
+            Some(descriptions.join("; "))
+        }
+    }
+
+    fn record(&mut self, input: &str, output: &str) {
+        if self.settings.history_limit == 0 {
+            return;
+        }
+        if self.history.len() >= self.settings.history_limit {
+            let excess = self.history.len() + 1 - self.settings.history_limit;
+            self.history.drain(..excess);
+        }
+        self.history.push(HistoryEntry {
+            input: input.to_owned(),
+            output: output.to_owned(),
+        });
+    }
+
+    /// The lines evaluated in this session, oldest first
+    pub fn history(&self) -> &[HistoryEntry] {
+        &self.history
+    }
+
+    /// The value of a variable, e.g. 3 after "str = 3"
+    pub fn variable(&self, name: &str) -> Option<i64> {
+        self.variables.get(name).copied()
+    }
+
+    /// Every variable set in this session
+    pub fn variables(&self) -> &HashMap<String, i64> {
+        &self.variables
+    }
+
+    /// Every macro saved in this session, sorted by name
+    pub fn macros(&self) -> impl Iterator<Item = &Macro> {
+        self.macros.values()
+    }
+
+    /// Save a macro, replacing any macro with the same name
+    pub fn add_macro(&mut self, definition: Macro) {
+        self.macros.insert(definition.name.clone(), definition);
+    }
+
+    /// Limit how many values (dice, numbers, cards, etc.) the session
+    /// can output from now on, across every line it evaluates. Once it
+    /// has output more than that, every expression gives an error.
+    pub fn set_output_limit(&mut self, limit: Option<usize>) {
+        self.output_limit = limit;
+        self.output_len = 0;
+    }
+
+    /// Whether the session has output more values than its limit allows
+    pub fn output_limit_reached(&self) -> bool {
+        self.output_limit.is_some_and(|limit| self.output_len > limit)
+    }
+
+    /// The deck used by "draw", "deck shuffle", etc.
+    pub fn deck(&self) -> &Deck {
+        &self.deck
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub fn settings_mut(&mut self) -> &mut Settings {
+        &mut self.settings
+    }
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Session;
+
+    #[test]
+    fn deck_persists_between_evaluations() {
+        let mut session = Session::new();
+        session.eval("draw 5");
+        session.eval("draw 2");
+        assert_eq!(session.deck().remaining(), 45);
+    }
+
+    #[test]
+    fn history_is_limited() {
+        let mut session = Session::new();
+        session.settings_mut().history_limit = 2;
+        for expression in ["coin", "1-6", "perm 1-3"] {
+            session.eval(expression);
+        }
+        let inputs: Vec<_> = session.history().iter().map(|entry| entry.input.as_str()).collect();
+        assert_eq!(inputs, ["1-6", "perm 1-3"]);
+    }
+
+    #[test]
+    fn output_is_limited() {
+        let mut session = Session::new();
+        session.set_output_limit(Some(10));
+        assert!(session.evaluate("perm 1-5; 5d6").is_ok());
+        assert!(!session.output_limit_reached());
+        assert_eq!(session.eval("coin"), "Error: can't output more than 10 values");
+        assert!(session.output_limit_reached());
+        assert_eq!(session.eval("coin"), "Error: can't output more than 10 values");
+    }
+}
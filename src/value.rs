@@ -0,0 +1,37 @@
+use std::fmt::{self, Display};
+
+use crate::dice::atom::{write_atoms, DiceExpressionAtom};
+
+/// An integer in an expression that may only be known once the
+/// expression is evaluated, e.g. the upper bound of "1-maxhp"
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IntValue {
+    /// An integer written out in the expression, e.g. the "1" in "1-maxhp"
+    Literal(i64),
+    /// The value of a variable, e.g. the "maxhp" in "1-maxhp"
+    Variable(String),
+    /// The total of a dice expression in parentheses,
+    /// e.g. the "(n+1)" in "(n+1)d8"
+    Expression(Vec<DiceExpressionAtom>),
+}
+
+impl From<i64> for IntValue {
+    fn from(value: i64) -> Self {
+        IntValue::Literal(value)
+    }
+}
+
+impl Display for IntValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntValue::Literal(value) => write!(f, "{value}"),
+            IntValue::Variable(name) => f.write_str(name),
+            IntValue::Expression(atoms) => {
+                f.write_str("(")?;
+                write_atoms(f, atoms)?;
+                f.write_str(")")
+            }
+        }
+    }
+}